@@ -0,0 +1,467 @@
+//! Terminal front end to play and debug games where the desktop app is not available, like over
+//! SSH. Reads commands and moves line by line and answers with the board as a Unicode grid.
+use chess_core::draw::DrawReason;
+use chess_core::history::History;
+use chess_core::{engine, is_figures_turn, Board, FigureType, GameStatus, Move, Position};
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+const ENGINE_DEPTH: u32 = 4;
+const ENGINE_TIME: Duration = Duration::from_secs(2);
+
+const HELP: &str = "\
+Moves are given in coordinates like e2e4 or e7e8q, or in SAN like Nf3 or exd8=Q.
+Commands:
+  board              show the board
+  options <square>   show where the piece on the square may move
+  moves              list all legal moves
+  undo, redo         take back or replay a move
+  engine <color>     let the engine play white or black, or `engine off`
+  go                 let the engine play the next move
+  draw               claim a draw by repetition or the fifty-move rule
+  resign             give up the game
+  new                start a new game
+  fen [<fen>]        show the position as FEN or set up another one
+  pgn                show the game as PGN
+  quit               leave";
+
+fn main() -> io::Result<()> {
+    run(io::stdin().lock(), io::stdout())
+}
+
+/// Reads commands from `input` until `quit` or the end of input and answers on `output`.
+fn run<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    let mut session = Session::new();
+    writeln!(output, "{}", session.show())?;
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if matches!(line, "quit" | "exit") {
+            break;
+        }
+        match session.execute(line) {
+            Ok(reply) if reply.is_empty() => {}
+            Ok(reply) => writeln!(output, "{}", reply)?,
+            Err(e) => writeln!(output, "error: {}", e)?,
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    writeln!(output)
+}
+
+/// A game played in the terminal.
+struct Session {
+    board: Board,
+    history: History,
+    /// Color the engine plays, if any
+    engine: Option<bool>,
+}
+
+impl Session {
+    fn new() -> Session {
+        let board = Board::init();
+        Session {
+            history: History::new(&board),
+            board,
+            engine: None,
+        }
+    }
+
+    /// Runs a command or plays a move and returns what to print.
+    fn execute(&mut self, line: &str) -> Result<String, String> {
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        let args = args.trim();
+        match command {
+            "" => Ok(String::new()),
+            "help" => Ok(HELP.to_string()),
+            "board" => Ok(self.show()),
+            "options" => self.options(args),
+            "moves" => Ok(self.moves()),
+            "undo" => {
+                self.ensure_ongoing()?;
+                // Take back the engine's reply together with the own move
+                while self.history.undo(&mut self.board) && self.is_engines_turn() {}
+                // Back at the start of a game the engine opens, it moves again
+                Ok(self.show_after_engine_reply())
+            }
+            "redo" => {
+                self.ensure_ongoing()?;
+                while self.history.redo(&mut self.board) && self.is_engines_turn() {}
+                Ok(self.show_after_engine_reply())
+            }
+            "engine" => {
+                self.engine = match args {
+                    "white" => Some(true),
+                    "black" => Some(false),
+                    "off" => None,
+                    _ => return Err("expected white, black or off".to_string()),
+                };
+                Ok(self.engine_reply())
+            }
+            "go" => {
+                self.ensure_ongoing()?;
+                let result = engine::search(&self.board, ENGINE_DEPTH, ENGINE_TIME)
+                    .ok_or("no legal move")?;
+                Ok(self.play(result.best_move))
+            }
+            "draw" => {
+                self.ensure_ongoing()?;
+                let reason = self.board.claimable_draw().ok_or("no draw to claim")?;
+                self.board.status = GameStatus::Draw(reason);
+                Ok(self.status())
+            }
+            "resign" => {
+                self.ensure_ongoing()?;
+                let white = match self.engine {
+                    Some(engine_white) => !engine_white,
+                    None => is_figures_turn(true, self.board.round),
+                };
+                self.board.status = GameStatus::Resignation { white };
+                Ok(self.status())
+            }
+            "new" => {
+                *self = Session {
+                    engine: self.engine,
+                    ..Session::new()
+                };
+                Ok(self.show_after_engine_reply())
+            }
+            "fen" if args.is_empty() => Ok(self.board.to_fen()),
+            "fen" => {
+                self.board = Board::from_fen(args)?;
+                self.history = History::new(&self.board);
+                Ok(self.show_after_engine_reply())
+            }
+            "pgn" => Ok(self.history.to_pgn(&self.board).trim_end().to_string()),
+            _ => {
+                self.ensure_ongoing()?;
+                let m = parse_move(&self.board, line)?;
+                let reply = self.play(m);
+                let played = self.last_move();
+                match self.engine_reply() {
+                    engine_reply if engine_reply.is_empty() => Ok(reply),
+                    engine_reply => Ok(format!("{}\n{}", played, engine_reply)),
+                }
+            }
+        }
+    }
+
+    fn is_engines_turn(&self) -> bool {
+        self.engine
+            .is_some_and(|white| is_figures_turn(white, self.board.round))
+    }
+
+    fn ensure_ongoing(&self) -> Result<(), String> {
+        if self.board.status.is_decided() {
+            Err("the game is over, start a new one with `new`".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Plays a legal move and shows the resulting position.
+    fn play(&mut self, m: Move) -> String {
+        self.history
+            .play(&mut self.board, m.from, m.to, m.promotion);
+        format!("{}\n{}", self.last_move(), self.show())
+    }
+
+    /// Lets the engine move if it is its turn, returning what to print.
+    fn engine_reply(&mut self) -> String {
+        if !self.is_engines_turn() || self.board.status.is_decided() {
+            return String::new();
+        }
+        match engine::search(&self.board, ENGINE_DEPTH, ENGINE_TIME) {
+            Some(result) => self.play(result.best_move),
+            None => String::new(),
+        }
+    }
+
+    fn show_after_engine_reply(&mut self) -> String {
+        match self.engine_reply() {
+            reply if reply.is_empty() => self.show(),
+            reply => reply,
+        }
+    }
+
+    /// The last move with its number, like `12... Nxe4+`.
+    fn last_move(&self) -> String {
+        match self.history.moves.last() {
+            Some(played) => {
+                let round = self.board.round - 1;
+                let dots = if is_figures_turn(true, round) {
+                    "."
+                } else {
+                    "..."
+                };
+                format!("{}{} {}", round / 2 + 1, dots, played.san)
+            }
+            None => String::new(),
+        }
+    }
+
+    fn show(&self) -> String {
+        format!("{}\n{}", render(&self.board, &[], &[]), self.status())
+    }
+
+    fn status(&self) -> String {
+        let side = |white: bool| if white { "White" } else { "Black" };
+        let to_move = is_figures_turn(true, self.board.round);
+        match self.board.status {
+            GameStatus::Ongoing => format!("{} to move", side(to_move)),
+            GameStatus::Check => format!("{} to move, check!", side(to_move)),
+            GameStatus::Checkmate => format!("Checkmate, {} wins", side(!to_move)),
+            GameStatus::Stalemate => "Stalemate, the game is drawn".to_string(),
+            GameStatus::Timeout => format!("{} lost on time", side(to_move)),
+            GameStatus::Draw(reason) => format!("Draw by {}", draw_reason(reason)),
+            GameStatus::Resignation { white } => format!("{} resigned", side(white)),
+        }
+    }
+
+    /// Where the piece on the square may move, marked on the board and listed.
+    fn options(&self, square: &str) -> Result<String, String> {
+        let position = Position::from_square(square).ok_or("expected a square like e2")?;
+        let figure = self
+            .board
+            .get_figure_from_position(position)
+            .ok_or_else(|| format!("there is no piece on {}", square))?;
+        if !is_figures_turn(figure.white, self.board.round) {
+            return Err(format!("the piece on {} is not the side to move", square));
+        }
+        let options = figure.get_legal_move_options(&self.board);
+        let list = |positions: &[Position]| match positions {
+            [] => "-".to_string(),
+            _ => positions
+                .iter()
+                .map(|p| p.to_square())
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        Ok(format!(
+            "{}\nmoves: {}\ncaptures: {}",
+            render(&self.board, &options.movable, &options.killable),
+            list(&options.movable),
+            list(&options.killable)
+        ))
+    }
+
+    fn moves(&self) -> String {
+        let moves: Vec<String> = self
+            .board
+            .legal_moves()
+            .iter()
+            .map(|m| {
+                let san = self.board.san(m.from, m.to);
+                match &m.promotion {
+                    // The promotion letter of UCI, like the `q` of `e7e8q`
+                    Some(_) => format!("{}={}", san, m.to_uci()[4..].to_uppercase()),
+                    None => san,
+                }
+            })
+            .collect();
+        match moves.len() {
+            0 => "no legal moves".to_string(),
+            _ => moves.join(" "),
+        }
+    }
+}
+
+/// Reads a legal move of the side to move in coordinates or SAN.
+fn parse_move(board: &Board, text: &str) -> Result<Move, String> {
+    let m = match Move::from_uci(text) {
+        Some(m) => m,
+        None => {
+            let (from, to, promotion) = board
+                .parse_san(text)
+                .map_err(|e| format!("{}: {}, see `help`", text, e))?;
+            return Ok(Move {
+                from,
+                to,
+                promotion,
+            });
+        }
+    };
+    let legal = board.legal_moves();
+    if legal.contains(&m) {
+        Ok(m)
+    } else if m.promotion.is_none() && legal.iter().any(|l| l.from == m.from && l.to == m.to) {
+        Err(format!(
+            "{}: add the piece to promote to, like {}q",
+            text, text
+        ))
+    } else {
+        Err(format!("{}: illegal move", text))
+    }
+}
+
+/// The board with white at the bottom, marking empty target squares with `*` and pieces that
+/// can be captured with brackets.
+fn render(board: &Board, movable: &[Position], killable: &[Position]) -> String {
+    let mut rows = vec![];
+    for y in (0..8).rev() {
+        let mut row = format!("{} ", y + 1);
+        for x in 0..8 {
+            let position = Position::new(x, y);
+            let symbol = match board.get_figure_from_position(position) {
+                Some(figure) => symbol(&figure.kind, figure.white),
+                None if movable.contains(&position) => '*',
+                None => '·',
+            };
+            if killable.contains(&position) {
+                row.push('[');
+                row.push(symbol);
+                row.push(']');
+            } else {
+                row.push(' ');
+                row.push(symbol);
+                row.push(' ');
+            }
+        }
+        rows.push(row.trim_end().to_string());
+    }
+    rows.push("   a  b  c  d  e  f  g  h".to_string());
+    rows.join("\n")
+}
+
+fn symbol(kind: &FigureType, white: bool) -> char {
+    let symbols = match kind {
+        FigureType::King => ['♔', '♚'],
+        FigureType::Queen => ['♕', '♛'],
+        FigureType::Rook => ['♖', '♜'],
+        FigureType::Bishop => ['♗', '♝'],
+        FigureType::Knight => ['♘', '♞'],
+        FigureType::Pawn => ['♙', '♟'],
+    };
+    symbols[!white as usize]
+}
+
+fn draw_reason(reason: DrawReason) -> &'static str {
+    match reason {
+        DrawReason::ThreefoldRepetition => "threefold repetition",
+        DrawReason::FivefoldRepetition => "fivefold repetition",
+        DrawReason::FiftyMoveRule => "the fifty-move rule",
+        DrawReason::SeventyFiveMoveRule => "the seventy-five-move rule",
+        DrawReason::InsufficientMaterial => "insufficient material",
+        DrawReason::Agreement => "agreement",
+        DrawReason::TimeoutVsInsufficientMaterial => "timeout without mating material",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn after(moves: &[&str]) -> Session {
+        let mut session = Session::new();
+        for m in moves {
+            session.execute(m).unwrap();
+        }
+        session
+    }
+
+    #[test]
+    fn renders_board() {
+        let board = render(&Board::init(), &[], &[]);
+        let rows: Vec<&str> = board.lines().collect();
+        assert_eq!(rows[0], "8  ♜  ♞  ♝  ♛  ♚  ♝  ♞  ♜");
+        assert_eq!(rows[2], "6  ·  ·  ·  ·  ·  ·  ·  ·");
+        assert_eq!(rows[7], "1  ♖  ♘  ♗  ♕  ♔  ♗  ♘  ♖");
+        assert_eq!(rows[8], "   a  b  c  d  e  f  g  h");
+    }
+
+    #[test]
+    fn plays_coordinates_and_san() {
+        let mut session = after(&["e2e4", "e5", "Nf3"]);
+        assert_eq!(
+            session.board.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+        let reply = session.execute("Nc6").unwrap();
+        assert!(reply.starts_with("2... Nc6\n"));
+        assert!(reply.ends_with("White to move"));
+
+        assert!(session
+            .execute("e4e5")
+            .unwrap_err()
+            .contains("illegal move"));
+        assert!(session.execute("Ke3").unwrap_err().contains("illegal move"));
+        assert!(session.execute("Zz9").is_err());
+
+        session.execute("undo").unwrap();
+        assert!(session.execute("board").unwrap().ends_with("Black to move"));
+    }
+
+    #[test]
+    fn shows_options() {
+        let mut session = after(&["e4", "d5"]);
+        let reply = session.execute("options e4").unwrap();
+        assert!(reply.contains("5  ·  ·  · [♟] *  ·  ·  ·"));
+        assert!(reply.ends_with("moves: e5\ncaptures: d5"));
+        assert!(session.execute("options d5").is_err());
+        assert!(session.execute("options e3").is_err());
+        assert!(session.execute("moves").unwrap().contains("exd5"));
+    }
+
+    #[test]
+    fn game_flow_until_the_end() {
+        let mut session = after(&["f3", "e5", "g4"]);
+        let reply = session.execute("Qh4").unwrap();
+        assert!(reply.starts_with("2... Qh4#\n"));
+        assert!(reply.ends_with("Checkmate, Black wins"));
+        assert!(session.execute("a3").unwrap_err().contains("game is over"));
+        assert!(session.execute("pgn").unwrap().ends_with("0-1"));
+
+        let mut session = after(&["fen 4k3/1P6/8/8/8/8/8/4K3 w - - 0 1"]);
+        assert!(session.execute("b7b8").unwrap_err().contains("b7b8q"));
+        session.execute("b7b8r").unwrap();
+        assert_eq!(
+            session.execute("fen").unwrap(),
+            "1R2k3/8/8/8/8/8/8/4K3 b - - 0 1"
+        );
+        assert_eq!(session.execute("resign").unwrap(), "Black resigned");
+        assert!(session
+            .execute("undo")
+            .unwrap_err()
+            .contains("game is over"));
+        assert!(session
+            .execute("redo")
+            .unwrap_err()
+            .contains("game is over"));
+        assert_eq!(
+            session.execute("fen").unwrap(),
+            "1R2k3/8/8/8/8/8/8/4K3 b - - 0 1"
+        );
+        assert!(session.execute("pgn").unwrap().ends_with("1-0"));
+    }
+
+    #[test]
+    fn engine_replies() {
+        let mut session = Session::new();
+        session.execute("engine black").unwrap();
+        let reply = session.execute("e4").unwrap();
+        assert!(reply.starts_with("1. e4\n1... "));
+        assert_eq!(session.board.round, 2);
+        session.execute("undo").unwrap();
+        assert_eq!(session.board.round, 0);
+
+        let mut session = Session::new();
+        session.execute("engine white").unwrap();
+        assert_eq!(session.board.round, 1);
+        let reply = session.execute("undo").unwrap();
+        assert!(reply.starts_with("1. "));
+        assert_eq!(session.board.round, 1);
+    }
+
+    #[test]
+    fn runs_a_session() {
+        let mut output = vec![];
+        run(io::Cursor::new("e4\nhelp\nquit\ne5\n"), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("1. e4\n"));
+        assert!(output.contains("Commands:"));
+        assert!(!output.contains("1... e5"));
+    }
+}
@@ -0,0 +1,307 @@
+//! The built-in engine speaking UCI on stdin and stdout, for chess GUIs and engine tournaments.
+use chess_core::engine::{self, SearchResult, MATE};
+use chess_core::history::History;
+use chess_core::{is_figures_turn, Board};
+use std::io;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const MAX_DEPTH: u32 = 64;
+/// Stands in for no time limit, until `stop` is received
+const INFINITE: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+/// Moves the remaining clock time is divided over if the GUI doesn't say
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+
+struct RunningSearch {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl RunningSearch {
+    fn finish(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.thread().unpark();
+        let _ = self.handle.join();
+    }
+}
+
+fn main() {
+    run(io::stdin().lock(), io::stdout());
+}
+
+/// Reads UCI commands from `input` until `quit` or the end of input and answers on `output`.
+/// Searches run on their own thread, so `stop` and `isready` are answered while searching.
+fn run<R: BufRead, W: Write + Send + 'static>(input: R, output: W) {
+    let output = Arc::new(Mutex::new(output));
+    let mut board = Board::init();
+    let mut search: Option<RunningSearch> = None;
+    for line in input.lines().map_while(Result::ok) {
+        let mut tokens = line.split_whitespace();
+        let command = tokens.next();
+        // Only `isready` may be answered without waiting for a running search to end
+        if !matches!(command, Some("isready") | None) {
+            if let Some(running) = search.take() {
+                running.finish();
+            }
+        }
+        match command {
+            Some("uci") => send(
+                &output,
+                &format!(
+                    "id name {} {}\nid author {}\nuciok",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION"),
+                    env!("CARGO_PKG_AUTHORS")
+                ),
+            ),
+            Some("isready") => send(&output, "readyok"),
+            Some("ucinewgame") => board = Board::init(),
+            Some("position") => match parse_position(&tokens.collect::<Vec<_>>()) {
+                Ok(position) => board = position,
+                Err(e) => send(&output, &format!("info string {}", e)),
+            },
+            Some("go") => {
+                let (max_depth, time, until_stop) = parse_go(&tokens.collect::<Vec<_>>(), &board);
+                search = Some(start_search(
+                    board.clone(),
+                    max_depth,
+                    time,
+                    until_stop,
+                    &output,
+                ));
+            }
+            Some("quit") => break,
+            // `stop` and `ponderhit` only need the running search to end, which happened above
+            _ => {}
+        }
+    }
+    if let Some(running) = search {
+        running.finish();
+    }
+}
+
+fn send<W: Write>(output: &Mutex<W>, message: &str) {
+    let mut output = output.lock().unwrap();
+    let _ = writeln!(output, "{}", message).and_then(|_| output.flush());
+}
+
+/// Parses the arguments of `position`, like `startpos moves e2e4 e7e5`.
+fn parse_position(args: &[&str]) -> Result<Board, String> {
+    let (fen, moves) = match args {
+        ["startpos", rest @ ..] => (Board::init().to_fen(), rest),
+        ["fen", rest @ ..] => {
+            let fields = rest.iter().take_while(|&&arg| arg != "moves").count();
+            (rest[..fields].join(" "), &rest[fields..])
+        }
+        _ => return Err("expected startpos or fen".to_string()),
+    };
+    let mut board = Board::from_fen(&fen)?;
+    let mut history = History::new(&board);
+    let moves = match moves {
+        ["moves", moves @ ..] => moves,
+        [] => &[],
+        _ => return Err("expected moves".to_string()),
+    };
+    for &token in moves {
+        let m = board
+            .legal_moves()
+            .into_iter()
+            .find(|m| m.to_uci() == token)
+            .ok_or_else(|| format!("illegal move {}", token))?;
+        history.play(&mut board, m.from, m.to, m.promotion);
+    }
+    Ok(board)
+}
+
+/// Search depth and time for the arguments of `go`, and whether `bestmove` has to wait for
+/// `stop` as for `go infinite` and `go ponder`. Without a limit the search runs until `stop`.
+fn parse_go(args: &[&str], board: &Board) -> (u32, Duration, bool) {
+    let value = |name: &str| {
+        args.iter()
+            .position(|&arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            // GUIs send negative times once a flag fell
+            .and_then(|value| value.parse::<i64>().ok())
+            .map(|value| value.max(0) as u64)
+    };
+    let until_stop = args.iter().any(|&arg| arg == "infinite" || arg == "ponder");
+    let max_depth = value("depth").map_or(MAX_DEPTH, |depth| depth.clamp(1, 64) as u32);
+    let white = is_figures_turn(true, board.round);
+    let (clock, increment) = if white {
+        (value("wtime"), value("winc"))
+    } else {
+        (value("btime"), value("binc"))
+    };
+    let time = match (value("movetime"), clock) {
+        (Some(movetime), _) => Duration::from_millis(movetime),
+        (None, Some(clock)) => {
+            let moves_to_go = value("movestogo").unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+            let budget = clock / moves_to_go + increment.unwrap_or(0) / 2;
+            // Keep a safety margin so the flag never falls
+            Duration::from_millis(budget.min(clock / 2))
+        }
+        (None, None) => INFINITE,
+    };
+    (max_depth, time, until_stop)
+}
+
+fn start_search<W: Write + Send + 'static>(
+    board: Board,
+    max_depth: u32,
+    time: Duration,
+    until_stop: bool,
+    output: &Arc<Mutex<W>>,
+) -> RunningSearch {
+    let stop = Arc::new(AtomicBool::new(false));
+    let handle = {
+        let stop = stop.clone();
+        let output = output.clone();
+        thread::spawn(move || {
+            let result = engine::search_until_stopped(&board, max_depth, time, &stop);
+            if let Some(result) = &result {
+                send(&output, &info(result));
+            }
+            // A search that ends by itself, like on finding a mate, may not answer before `stop`
+            while until_stop && !stop.load(Ordering::Relaxed) {
+                thread::park();
+            }
+            match result {
+                Some(result) => send(&output, &format!("bestmove {}", result.best_move.to_uci())),
+                None => send(&output, "bestmove 0000"),
+            }
+        })
+    };
+    RunningSearch { stop, handle }
+}
+
+fn info(result: &SearchResult) -> String {
+    let score = if result.score.abs() >= MATE - MAX_DEPTH as i32 {
+        let moves = (MATE - result.score.abs() + 1) / 2;
+        format!("mate {}", moves * result.score.signum())
+    } else {
+        format!("cp {}", result.score)
+    };
+    format!(
+        "info depth {} score {} nodes {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        result.best_move.to_uci()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn session(commands: &str) -> Vec<String> {
+        let output = SharedBuffer::default();
+        run(Cursor::new(commands.to_string()), output.clone());
+        let bytes = output.0.lock().unwrap().clone();
+        String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn handshake() {
+        let lines = session("uci\nisready\nquit\n");
+        assert!(lines[0].starts_with("id name chess_core"));
+        assert_eq!(lines[2], "uciok");
+        assert_eq!(lines[3], "readyok");
+    }
+
+    #[test]
+    fn reports_mate_scores() {
+        let board = parse_position(&["fen", "6k1/5ppp/8/8/8/8/8/R5K1", "w", "-", "-", "0", "1"]);
+        let result = engine::search(&board.unwrap(), 3, Duration::from_secs(60)).unwrap();
+        assert!(info(&result).starts_with("info depth 2 score mate 1 nodes "));
+        assert!(info(&result).ends_with(" pv a1a8"));
+    }
+
+    #[test]
+    fn plays_after_moves() {
+        let lines =
+            session("position startpos moves e2e4 e7e5 g1f3\ngo movetime 50\nisready\nquit\n");
+        let bestmove = lines.iter().find(|l| l.starts_with("bestmove")).unwrap();
+        let mut board = parse_position(&["startpos", "moves", "e2e4", "e7e5", "g1f3"]).unwrap();
+        let m = bestmove.split(' ').nth(1).unwrap();
+        assert!(board.legal_moves().iter().any(|legal| legal.to_uci() == m));
+        assert!(!is_figures_turn(true, board.round));
+
+        board = parse_position(&["fen", "3k4/1P6/8/8/8/8/8/4K3", "w", "-", "-", "0", "1"]).unwrap();
+        assert_eq!(board.to_fen(), "3k4/1P6/8/8/8/8/8/4K3 w - - 0 1");
+    }
+
+    #[test]
+    fn stop_ends_infinite_search() {
+        let lines = session("position startpos\ngo infinite\nstop\nquit\n");
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+        assert_eq!(
+            lines.iter().filter(|l| l.starts_with("bestmove")).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn infinite_search_waits_for_stop() {
+        let board = parse_position(&["fen", "6k1/5ppp/8/8/8/8/8/R5K1", "w", "-", "-", "0", "1"]);
+        let output = Arc::new(Mutex::new(SharedBuffer::default()));
+        let search = start_search(board.unwrap(), MAX_DEPTH, INFINITE, true, &output);
+        thread::sleep(Duration::from_millis(200));
+        let printed = |output: &Arc<Mutex<SharedBuffer>>| {
+            let bytes = output.lock().unwrap().0.lock().unwrap().clone();
+            String::from_utf8(bytes).unwrap()
+        };
+        assert!(!printed(&output).contains("bestmove"));
+        search.finish();
+        assert!(printed(&output).ends_with("bestmove a1a8\n"));
+    }
+
+    #[test]
+    fn position_errors() {
+        assert!(parse_position(&[]).is_err());
+        assert!(parse_position(&["startpos", "moves", "e2e5"]).is_err());
+        assert!(parse_position(&["fen", "8/8", "w"]).is_err());
+        let lines = session("position startpos moves e7e5\ngo depth 1\n");
+        assert_eq!(lines[0], "info string illegal move e7e5");
+    }
+
+    #[test]
+    fn go_time_management() {
+        let board = Board::init();
+        assert_eq!(parse_go(&["depth", "5"], &board), (5, INFINITE, false));
+        assert_eq!(
+            parse_go(&["movetime", "300"], &board),
+            (MAX_DEPTH, Duration::from_millis(300), false)
+        );
+        assert_eq!(parse_go(&["infinite"], &board), (MAX_DEPTH, INFINITE, true));
+        let args = ["wtime", "-500", "btime", "1000"];
+        assert_eq!(parse_go(&args, &board).1, Duration::ZERO);
+        let (_, time, _) = parse_go(&["depth", "1", "movetime", &u64::MAX.to_string()], &board);
+        assert!(engine::search(&board, 1, time).is_some());
+        let args = ["wtime", "60000", "btime", "1000", "winc", "2000"];
+        assert_eq!(parse_go(&args, &board).1, Duration::from_millis(3000));
+        let board = parse_position(&["startpos", "moves", "e2e4"]).unwrap();
+        assert_eq!(parse_go(&args, &board).1, Duration::from_millis(33));
+    }
+}
@@ -0,0 +1,278 @@
+//! Bitboards with one bit per square, where the square index is `y * 8 + x`. So a1 is the
+//! lowest and h8 the highest bit.
+use crate::{Board, FigureType, Position};
+
+pub(crate) type Bitboard = u64;
+
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (-2, 1),
+    (-1, 2),
+    (1, 2),
+    (2, 1),
+    (1, -2),
+    (2, -1),
+    (-2, -1),
+    (-1, -2),
+];
+const KING_OFFSETS: [(i32, i32); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+/// Ray directions, the first four point towards higher squares
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (-1, 1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (1, -1),
+];
+const ROOK_DIRECTIONS: [usize; 4] = [0, 2, 4, 6];
+const BISHOP_DIRECTIONS: [usize; 4] = [1, 3, 5, 7];
+
+const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_OFFSETS);
+const KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_OFFSETS);
+/// Squares attacked by a pawn, indexed by color with white at index 1
+const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&[(-1, -1), (1, -1)]),
+    leaper_attacks(&[(-1, 1), (1, 1)]),
+];
+const RAYS: [[Bitboard; 64]; 8] = rays();
+
+const fn leaper_attacks(offsets: &[(i32, i32)]) -> [Bitboard; 64] {
+    let mut table = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let (x, y) = ((square % 8) as i32, (square / 8) as i32);
+        let mut i = 0;
+        while i < offsets.len() {
+            let (tx, ty) = (x + offsets[i].0, y + offsets[i].1);
+            if tx >= 0 && tx < 8 && ty >= 0 && ty < 8 {
+                table[square] |= 1 << (ty * 8 + tx);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const fn rays() -> [[Bitboard; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (dx, dy) = DIRECTIONS[direction];
+        let mut square = 0;
+        while square < 64 {
+            let (mut x, mut y) = ((square % 8) as i32 + dx, (square / 8) as i32 + dy);
+            while x >= 0 && x < 8 && y >= 0 && y < 8 {
+                table[direction][square] |= 1 << (y * 8 + x);
+                x += dx;
+                y += dy;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    table
+}
+
+/// Attacks along a ray up to and including the first blocker.
+fn ray_attacks(direction: usize, square: usize, occupied: Bitboard) -> Bitboard {
+    let ray = RAYS[direction][square];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+    let blocker = if direction < 4 {
+        blockers.trailing_zeros() as usize
+    } else {
+        63 - blockers.leading_zeros() as usize
+    };
+    ray ^ RAYS[direction][blocker]
+}
+
+fn slider_attacks(directions: &[usize], square: usize, occupied: Bitboard) -> Bitboard {
+    directions
+        .iter()
+        .fold(0, |attacks, &d| attacks | ray_attacks(d, square, occupied))
+}
+
+pub(crate) fn bit(position: Position) -> Bitboard {
+    square(position).map_or(0, |square| 1 << square)
+}
+
+pub(crate) fn square(position: Position) -> Option<usize> {
+    if (0..8).contains(&position.x) && (0..8).contains(&position.y) {
+        Some((position.y * 8 + position.x) as usize)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn position(square: usize) -> Position {
+    Position::new((square % 8) as i32, (square / 8) as i32)
+}
+
+/// Positions of the set bits from the lowest square upwards.
+pub(crate) fn positions(mut bitboard: Bitboard) -> Vec<Position> {
+    let mut positions = vec![];
+    while bitboard != 0 {
+        positions.push(position(bitboard.trailing_zeros() as usize));
+        bitboard &= bitboard - 1;
+    }
+    positions
+}
+
+/// Squares a figure standing on `square` attacks given the occupied squares.
+pub(crate) fn attacks(
+    kind: &FigureType,
+    white: bool,
+    square: usize,
+    occupied: Bitboard,
+) -> Bitboard {
+    match kind {
+        FigureType::Pawn => PAWN_ATTACKS[white as usize][square],
+        FigureType::Knight => KNIGHT_ATTACKS[square],
+        FigureType::King => KING_ATTACKS[square],
+        FigureType::Bishop => slider_attacks(&BISHOP_DIRECTIONS, square, occupied),
+        FigureType::Rook => slider_attacks(&ROOK_DIRECTIONS, square, occupied),
+        FigureType::Queen => {
+            slider_attacks(&BISHOP_DIRECTIONS, square, occupied)
+                | slider_attacks(&ROOK_DIRECTIONS, square, occupied)
+        }
+    }
+}
+
+impl FigureType {
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            FigureType::Pawn => 0,
+            FigureType::King => 1,
+            FigureType::Queen => 2,
+            FigureType::Bishop => 3,
+            FigureType::Knight => 4,
+            FigureType::Rook => 5,
+        }
+    }
+}
+
+/// Alive figures of a board by color and kind, kept in sync with `Board::figures`.
+#[derive(Clone, Debug)]
+pub(crate) struct Bitboards {
+    colors: [Bitboard; 2],
+    kinds: [Bitboard; 6],
+    /// Index into `Board::figures` of the figure on each square
+    figure_at: [Option<u8>; 64],
+}
+
+impl Default for Bitboards {
+    fn default() -> Self {
+        Bitboards {
+            colors: [0; 2],
+            kinds: [0; 6],
+            figure_at: [None; 64],
+        }
+    }
+}
+
+impl Bitboards {
+    pub(crate) fn color(&self, white: bool) -> Bitboard {
+        self.colors[white as usize]
+    }
+
+    pub(crate) fn kind(&self, kind: &FigureType) -> Bitboard {
+        self.kinds[kind.index()]
+    }
+
+    pub(crate) fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    pub(crate) fn figure_at(&self, position: Position) -> Option<usize> {
+        square(position).and_then(|square| self.figure_at[square].map(usize::from))
+    }
+}
+
+impl Board {
+    /// Rebuilds the bitboards, needed after every change to the figures.
+    pub(crate) fn update_bitboards(&mut self) {
+        let mut bitboards = Bitboards::default();
+        for (i, figure) in self.figures.iter().enumerate() {
+            if let (true, Some(square)) = (figure.alive, square(figure.position)) {
+                bitboards.colors[figure.white as usize] |= 1 << square;
+                bitboards.kinds[figure.kind.index()] |= 1 << square;
+                bitboards.figure_at[square] = Some(i as u8);
+            }
+        }
+        self.bitboards = bitboards;
+    }
+
+    /// Squares of the figures of the given color attacking the position.
+    pub(crate) fn attackers(&self, position: Position, by_white: bool) -> Bitboard {
+        let square = match square(position) {
+            Some(square) => square,
+            None => return 0,
+        };
+        let bitboards = &self.bitboards;
+        let occupied = bitboards.occupied();
+        let diagonal = bitboards.kind(&FigureType::Bishop) | bitboards.kind(&FigureType::Queen);
+        let straight = bitboards.kind(&FigureType::Rook) | bitboards.kind(&FigureType::Queen);
+        // A pawn attacks the square if a pawn of the other color on it would attack the pawn
+        let attackers = (PAWN_ATTACKS[!by_white as usize][square]
+            & bitboards.kind(&FigureType::Pawn))
+            | (KNIGHT_ATTACKS[square] & bitboards.kind(&FigureType::Knight))
+            | (KING_ATTACKS[square] & bitboards.kind(&FigureType::King))
+            | (slider_attacks(&BISHOP_DIRECTIONS, square, occupied) & diagonal)
+            | (slider_attacks(&ROOK_DIRECTIONS, square, occupied) & straight);
+        attackers & bitboards.color(by_white)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaper_tables() {
+        assert_eq!(
+            KNIGHT_ATTACKS[0],
+            bit(Position::new(1, 2)) | bit(Position::new(2, 1))
+        );
+        assert_eq!(KING_ATTACKS[63].count_ones(), 3);
+        assert_eq!(PAWN_ATTACKS[1][8], bit(Position::new(1, 2)));
+        assert_eq!(PAWN_ATTACKS[0][15], bit(Position::new(6, 0)));
+    }
+
+    #[test]
+    fn sliding_attacks_stop_at_blockers() {
+        let occupied = bit(Position::new(3, 5)) | bit(Position::new(6, 3));
+        let rook = attacks(&FigureType::Rook, true, 27, occupied);
+        assert_eq!(
+            positions(rook),
+            vec![
+                Position::new(3, 0),
+                Position::new(3, 1),
+                Position::new(3, 2),
+                Position::new(0, 3),
+                Position::new(1, 3),
+                Position::new(2, 3),
+                Position::new(4, 3),
+                Position::new(5, 3),
+                Position::new(6, 3),
+                Position::new(3, 4),
+                Position::new(3, 5),
+            ]
+        );
+        assert_eq!(attacks(&FigureType::Bishop, true, 0, 0).count_ones(), 7);
+        assert_eq!(attacks(&FigureType::Queen, true, 27, 0).count_ones(), 27);
+    }
+}
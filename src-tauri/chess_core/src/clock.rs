@@ -0,0 +1,234 @@
+//! Chess clocks with Fischer and Bronstein time controls.
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// How the increment of a time control is given.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum IncrementKind {
+    /// The increment is added after every move
+    Fischer,
+    /// Time used up to the increment is given back, so a clock never gains time
+    Bronstein,
+}
+
+/// Time control in milliseconds, like 3 minutes plus a 2 second Fischer increment.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TimeControl {
+    /// Time each player starts with
+    pub base_ms: u64,
+    /// Time added or given back per move
+    pub increment_ms: u64,
+    /// How the increment is given
+    pub increment_kind: IncrementKind,
+}
+
+/// Remaining time of both players, as shown to the frontend.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ClockTimes {
+    /// Remaining time of white
+    pub white_ms: u64,
+    /// Remaining time of black
+    pub black_ms: u64,
+    /// Color of the player whose clock is running
+    pub running: Option<bool>,
+}
+
+/// Clocks of both players, of which at most one runs.
+#[derive(Serialize, Clone, Debug)]
+pub struct Clock {
+    time_control: TimeControl,
+    /// Remaining time by color with white at index 1, without the running turn
+    remaining: [Duration; 2],
+    running: Option<bool>,
+    #[serde(skip)]
+    turn_started: Option<Instant>,
+}
+
+impl Clock {
+    /// Stopped clocks with the base time of the time control.
+    pub fn new(time_control: TimeControl) -> Clock {
+        let base = Duration::from_millis(time_control.base_ms);
+        Clock {
+            time_control,
+            remaining: [base; 2],
+            running: None,
+            turn_started: None,
+        }
+    }
+
+    /// Stopped clocks continuing with the given remaining times, like for a saved game.
+    pub fn resume(time_control: TimeControl, times: ClockTimes) -> Clock {
+        Clock {
+            remaining: [
+                Duration::from_millis(times.black_ms),
+                Duration::from_millis(times.white_ms),
+            ],
+            ..Clock::new(time_control)
+        }
+    }
+
+    /// The time control the clocks were set up with.
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    /// Time used by the running player in the current turn.
+    fn charged(&self, white: bool, now: Instant) -> Duration {
+        match (self.running, self.turn_started) {
+            (Some(running), Some(started)) if running == white => {
+                now.saturating_duration_since(started)
+            }
+            _ => Duration::ZERO,
+        }
+    }
+
+    fn increment(&self) -> Duration {
+        Duration::from_millis(self.time_control.increment_ms)
+    }
+
+    /// Remaining time of the given player at `now`.
+    pub fn remaining(&self, white: bool, now: Instant) -> Duration {
+        self.remaining[white as usize].saturating_sub(self.charged(white, now))
+    }
+
+    /// Remaining time of both players at `now`.
+    pub fn times(&self, now: Instant) -> ClockTimes {
+        ClockTimes {
+            white_ms: self.remaining(true, now).as_millis() as u64,
+            black_ms: self.remaining(false, now).as_millis() as u64,
+            running: self.running,
+        }
+    }
+
+    /// Color of the player whose time ran out.
+    pub fn flag(&self, now: Instant) -> Option<bool> {
+        self.running
+            .filter(|&white| self.remaining(white, now) == Duration::ZERO)
+    }
+
+    /// Starts the clock of the given player, stopping the other one without an increment.
+    pub fn start(&mut self, white: bool, now: Instant) {
+        self.stop(now);
+        self.running = Some(white);
+        self.turn_started = Some(now);
+    }
+
+    /// Stops the running clock, like when the game is over.
+    pub fn stop(&mut self, now: Instant) {
+        if let Some(white) = self.running {
+            self.remaining[white as usize] = self.remaining(white, now);
+        }
+        self.running = None;
+        self.turn_started = None;
+    }
+
+    /// Ends the turn of the running player after a move and starts the opponent's clock.
+    pub fn press(&mut self, now: Instant) {
+        if let Some(white) = self.running {
+            let used = self.charged(white, now);
+            self.stop(now);
+            // A fallen flag gets no time back
+            if self.remaining[white as usize] > Duration::ZERO {
+                self.remaining[white as usize] += match self.time_control.increment_kind {
+                    IncrementKind::Fischer => self.increment(),
+                    IncrementKind::Bronstein => used.min(self.increment()),
+                };
+            }
+            self.start(!white, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(base_ms: u64, increment_ms: u64, increment_kind: IncrementKind) -> Clock {
+        Clock::new(TimeControl {
+            base_ms,
+            increment_ms,
+            increment_kind,
+        })
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn fischer_increment() {
+        let start = Instant::now();
+        let mut clock = clock(60_000, 2_000, IncrementKind::Fischer);
+        assert_eq!(clock.remaining(true, start + ms(5_000)), ms(60_000));
+
+        clock.start(true, start);
+        assert_eq!(clock.remaining(true, start + ms(5_000)), ms(55_000));
+        clock.press(start + ms(5_000));
+        assert_eq!(clock.remaining(true, start + ms(9_000)), ms(57_000));
+        assert_eq!(clock.remaining(false, start + ms(9_000)), ms(56_000));
+        assert_eq!(clock.running, Some(false));
+
+        clock.press(start + ms(10_000));
+        assert_eq!(
+            clock.times(start + ms(10_000)),
+            ClockTimes {
+                white_ms: 57_000,
+                black_ms: 57_000,
+                running: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn bronstein_delay() {
+        let start = Instant::now();
+        let mut clock = clock(60_000, 3_000, IncrementKind::Bronstein);
+        clock.start(true, start);
+        assert_eq!(clock.remaining(true, start + ms(2_000)), ms(58_000));
+        clock.press(start + ms(2_000));
+        assert_eq!(clock.remaining(true, start + ms(2_000)), ms(60_000));
+        clock.press(start + ms(7_000));
+        assert_eq!(clock.remaining(false, start + ms(7_000)), ms(58_000));
+    }
+
+    #[test]
+    fn bronstein_flag_falls_during_delay() {
+        let start = Instant::now();
+        let mut clock = clock(1_000, 3_000, IncrementKind::Bronstein);
+        clock.start(true, start);
+        assert_eq!(clock.flag(start + ms(999)), None);
+        assert_eq!(clock.flag(start + ms(1_000)), Some(true));
+        assert_eq!(clock.flag(start + ms(2_000)), Some(true));
+        clock.press(start + ms(2_000));
+        assert_eq!(clock.remaining(true, start + ms(2_000)), Duration::ZERO);
+    }
+
+    #[test]
+    fn flag_fall() {
+        let start = Instant::now();
+        let mut clock = clock(1_000, 0, IncrementKind::Fischer);
+        assert_eq!(clock.flag(start + ms(2_000)), None);
+        clock.start(false, start);
+        assert_eq!(clock.flag(start + ms(999)), None);
+        assert_eq!(clock.flag(start + ms(1_000)), Some(false));
+
+        clock.stop(start + ms(1_500));
+        assert_eq!(clock.flag(start + ms(1_500)), None);
+        assert_eq!(clock.remaining(false, start + ms(1_500)), Duration::ZERO);
+        assert_eq!(clock.remaining(true, start + ms(1_500)), ms(1_000));
+    }
+
+    #[test]
+    fn resume_saved_times() {
+        let start = Instant::now();
+        let mut clock = clock(60_000, 1_000, IncrementKind::Fischer);
+        clock.start(true, start);
+        let times = clock.times(start + ms(5_000));
+        let mut resumed = Clock::resume(clock.time_control(), times);
+        assert_eq!(resumed.times(start).running, None);
+        assert_eq!(resumed.remaining(true, start), ms(55_000));
+        resumed.start(false, start);
+        resumed.press(start + ms(2_000));
+        assert_eq!(resumed.remaining(false, start + ms(2_000)), ms(59_000));
+    }
+}
@@ -0,0 +1,183 @@
+//! Draws by repetition, the fifty- and seventy-five-move rules and insufficient material.
+use crate::{bitboard, Board, FigureType};
+use serde::{Deserialize, Serialize};
+
+/// Halfmoves without capture or pawn move after which a player may claim a draw
+const FIFTY_MOVE_RULE: u32 = 100;
+/// Halfmoves without capture or pawn move after which the game is drawn
+const SEVENTY_FIVE_MOVE_RULE: u32 = 150;
+
+const LIGHT_SQUARES: bitboard::Bitboard = 0x55AA_55AA_55AA_55AA;
+
+/// Why a game ended in a draw.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DrawReason {
+    /// Claimed after the same position occurred three times
+    ThreefoldRepetition,
+    /// The same position occurred five times
+    FivefoldRepetition,
+    /// Claimed after fifty moves of each side without a capture or pawn move
+    FiftyMoveRule,
+    /// Seventy-five moves of each side without a capture or pawn move
+    SeventyFiveMoveRule,
+    /// Neither side can checkmate anymore
+    InsufficientMaterial,
+    /// One player offered a draw and the other one accepted
+    Agreement,
+    /// A flag fell while the opponent had no pieces left to checkmate with
+    TimeoutVsInsufficientMaterial,
+}
+
+impl Board {
+    /// How often the current position occurred, counting itself.
+    pub fn repetitions(&self) -> usize {
+        match self.positions.last() {
+            Some(current) => self
+                .positions
+                .iter()
+                .filter(|&hash| hash == current)
+                .count(),
+            None => 0,
+        }
+    }
+
+    /// Whether no sequence of legal moves could lead to a checkmate, like king and bishop
+    /// against king.
+    pub fn has_insufficient_material(&self) -> bool {
+        let bitboards = &self.bitboards;
+        let heavy = bitboards.kind(&FigureType::Pawn)
+            | bitboards.kind(&FigureType::Rook)
+            | bitboards.kind(&FigureType::Queen);
+        if heavy != 0 {
+            return false;
+        }
+        let knights = bitboards.kind(&FigureType::Knight);
+        let bishops = bitboards.kind(&FigureType::Bishop);
+        // Bishops that all stand on squares of one color can't ever mate
+        let same_colored_bishops = bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0;
+        (knights | bishops).count_ones() <= 1 || (knights == 0 && same_colored_bishops)
+    }
+
+    /// Whether the pieces of the given color could checkmate by some series of legal moves,
+    /// which a lone king never can.
+    pub fn has_mating_material(&self, white: bool) -> bool {
+        let pieces = self.bitboards.color(white) & !self.bitboards.kind(&FigureType::King);
+        pieces != 0 && !self.has_insufficient_material()
+    }
+
+    /// Draw that ends the game without either player asking for it.
+    pub fn automatic_draw(&self) -> Option<DrawReason> {
+        if self.has_insufficient_material() {
+            Some(DrawReason::InsufficientMaterial)
+        } else if self.repetitions() >= 5 {
+            Some(DrawReason::FivefoldRepetition)
+        } else if self.halfmove_clock >= SEVENTY_FIVE_MOVE_RULE {
+            Some(DrawReason::SeventyFiveMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Draw the player to move may claim.
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.repetitions() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove_clock >= FIFTY_MOVE_RULE {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::from_pgn;
+    use crate::GameStatus;
+
+    fn board(fen: &str) -> Board {
+        Board::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn insufficient_material() {
+        for fen in [
+            "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3KB3/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3KN3/8/8 b - - 0 1",
+            "8/8/3bk3/8/8/3KB3/8/8 w - - 0 1",
+        ] {
+            let board = board(fen);
+            assert!(board.has_insufficient_material(), "{}", fen);
+            assert_eq!(
+                board.status,
+                GameStatus::Draw(DrawReason::InsufficientMaterial)
+            );
+        }
+        for fen in [
+            "8/8/4k3/8/8/3KP3/8/8 w - - 0 1",
+            "8/8/2b1k3/8/8/3KB3/8/8 w - - 0 1",
+            "8/8/4k3/8/8/2NKN3/8/8 w - - 0 1",
+            "8/8/2n1k3/8/8/3KB3/8/8 w - - 0 1",
+        ] {
+            assert!(!board(fen).has_insufficient_material(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn mating_material() {
+        let board = board("4k3/8/8/8/8/8/8/4KB2 w - - 0 1");
+        assert!(!board.has_mating_material(true));
+        assert!(!board.has_mating_material(false));
+        let board = self::board("4k3/4p3/8/8/8/8/8/4KB2 w - - 0 1");
+        assert!(board.has_mating_material(true));
+        assert!(board.has_mating_material(false));
+        let board = self::board("4k3/8/8/8/8/8/8/4KQ2 w - - 0 1");
+        assert!(board.has_mating_material(true));
+        assert!(!board.has_mating_material(false));
+    }
+
+    #[test]
+    fn move_rules() {
+        let board = board("8/8/4k3/8/8/3K1R2/8/8 w - - 99 80");
+        assert_eq!(board.claimable_draw(), None);
+        let board = self::board("8/8/4k3/8/8/3K1R2/8/8 w - - 100 80");
+        assert_eq!(board.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+        assert!(!board.status.is_decided());
+        let board = self::board("8/8/4k3/8/8/3K1R2/8/8 w - - 150 100");
+        assert_eq!(
+            board.status,
+            GameStatus::Draw(DrawReason::SeventyFiveMoveRule)
+        );
+        // Checkmate on the last move counts
+        let board = self::board("7k/8/8/8/8/8/8/K5RR b - - 150 100");
+        assert_eq!(board.status, GameStatus::Checkmate);
+    }
+
+    #[test]
+    fn repetitions() {
+        let shuffle = "1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 Nf6 4. Ng1 Ng8";
+        let (mut board, mut history) = from_pgn(shuffle).unwrap();
+        assert_eq!(board.repetitions(), 3);
+        assert_eq!(
+            board.claimable_draw(),
+            Some(DrawReason::ThreefoldRepetition)
+        );
+        assert!(!board.status.is_decided());
+
+        history.undo(&mut board);
+        assert_eq!(board.repetitions(), 2);
+        assert_eq!(board.claimable_draw(), None);
+
+        let (board, _) = from_pgn(&format!(
+            "{} 5. Nf3 Nf6 6. Ng1 Ng8 7. Nf3 Nf6 8. Ng1 Ng8",
+            shuffle
+        ))
+        .unwrap();
+        assert_eq!(
+            board.status,
+            GameStatus::Draw(DrawReason::FivefoldRepetition)
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! The built-in engine, an alpha-beta search with iterative deepening.
+use crate::{is_figures_turn, Board, FigureType, Move};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Score of checkmate, reduced by the number of halfmoves until it happens
+pub const MATE: i32 = 100_000;
+/// The engine accepts a draw offer when it is worse by more than this many centipawns
+const ACCEPT_DRAW_BELOW: i32 = -50;
+const DRAW_OFFER_DEPTH: u32 = 3;
+const DRAW_OFFER_TIME: Duration = Duration::from_millis(500);
+
+// Piece-square tables from white's point of view with rank 8 in the first row, see
+// https://www.chessprogramming.org/Simplified_Evaluation_Function
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+/// Outcome of the deepest completed iteration of a search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    /// Best move found for the side to move
+    pub best_move: Move,
+    /// Centipawns from the point of view of the side to move
+    pub score: i32,
+    /// Depth of the completed iteration in halfmoves
+    pub depth: u32,
+    /// Positions visited by the whole search
+    pub nodes: u64,
+}
+
+impl FigureType {
+    fn value(&self) -> i32 {
+        match self {
+            FigureType::Pawn => 100,
+            FigureType::Knight => 320,
+            FigureType::Bishop => 330,
+            FigureType::Rook => 500,
+            FigureType::Queen => 900,
+            FigureType::King => 0,
+        }
+    }
+
+    fn table(&self) -> &'static [i32; 64] {
+        match self {
+            FigureType::Pawn => &PAWN_TABLE,
+            FigureType::Knight => &KNIGHT_TABLE,
+            FigureType::Bishop => &BISHOP_TABLE,
+            FigureType::Rook => &ROOK_TABLE,
+            FigureType::Queen => &QUEEN_TABLE,
+            FigureType::King => &KING_TABLE,
+        }
+    }
+}
+
+impl Board {
+    /// Material and piece-square score from the point of view of the side to move.
+    fn evaluate(&self) -> i32 {
+        let score: i32 = self
+            .figures
+            .iter()
+            .filter(|figure| figure.alive)
+            .map(|figure| {
+                let row = if figure.white {
+                    7 - figure.position.y
+                } else {
+                    figure.position.y
+                };
+                let square = (row * 8 + figure.position.x) as usize;
+                let value = figure.kind.value() + figure.kind.table()[square];
+                if figure.white {
+                    value
+                } else {
+                    -value
+                }
+            })
+            .sum();
+        if is_figures_turn(true, self.round) {
+            score
+        } else {
+            -score
+        }
+    }
+
+    /// Value of the captured figure minus a fraction of the attacker, so that good captures
+    /// are searched first.
+    fn move_order_score(&self, m: &Move) -> i32 {
+        let attacker = self
+            .get_figure_from_position(m.from)
+            .map_or(0, |figure| figure.kind.value());
+        let victim = self
+            .get_figure_from_position(m.to)
+            .map_or(0, |figure| figure.kind.value());
+        let promotion = m.promotion.as_ref().map_or(0, |kind| kind.value());
+        if victim > 0 || promotion > 0 {
+            10 * (victim + promotion) - attacker / 10
+        } else {
+            0
+        }
+    }
+
+    fn ordered_moves(&self, first: Option<&Move>) -> Vec<Move> {
+        let mut moves = self.legal_moves();
+        moves.sort_by_cached_key(|m| {
+            if Some(m) == first {
+                i32::MIN
+            } else {
+                -self.move_order_score(m)
+            }
+        });
+        moves
+    }
+}
+
+struct Search<'a> {
+    /// `None` for a time too far away to be represented
+    deadline: Option<Instant>,
+    /// Set from outside to end the search early
+    stop: &'a AtomicBool,
+    stopped: bool,
+    nodes: u64,
+}
+
+impl Search<'_> {
+    fn should_stop(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            || self.stop.load(Ordering::Relaxed)
+    }
+
+    fn out_of_time(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(256) && self.should_stop() {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+
+    fn negamax(&mut self, board: &Board, depth: u32, mut alpha: i32, beta: i32, ply: i32) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
+        if depth == 0 {
+            return self.quiescence(board, alpha, beta);
+        }
+        let moves = board.ordered_moves(None);
+        if moves.is_empty() {
+            let white = is_figures_turn(true, board.round);
+            return if board.is_in_check(white) {
+                -MATE + ply
+            } else {
+                0
+            };
+        }
+        for m in moves {
+            let score = -self.negamax(&board.after_move(&m), depth - 1, -beta, -alpha, ply + 1);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// Resolves captures at the horizon so that hanging figures are not misjudged.
+    fn quiescence(&mut self, board: &Board, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
+        let stand_pat = board.evaluate();
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+        for m in board.ordered_moves(None) {
+            if board.move_order_score(&m) <= 0 {
+                break;
+            }
+            let score = -self.quiescence(&board.after_move(&m), -beta, -alpha);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+}
+
+/// Iterative deepening alpha-beta search over the legal moves of the side to move. Returns the
+/// result of the deepest completed iteration; the first iteration always completes.
+pub fn search(board: &Board, max_depth: u32, time: Duration) -> Option<SearchResult> {
+    search_until_stopped(board, max_depth, time, &AtomicBool::new(false))
+}
+
+/// Like `search`, but also ends once `stop` is set.
+pub fn search_until_stopped(
+    board: &Board,
+    max_depth: u32,
+    time: Duration,
+    stop: &AtomicBool,
+) -> Option<SearchResult> {
+    let mut search = Search {
+        deadline: Instant::now().checked_add(time),
+        stop,
+        stopped: false,
+        nodes: 0,
+    };
+    let mut result: Option<SearchResult> = None;
+    for depth in 1..=max_depth.max(1) {
+        let moves = board.ordered_moves(result.as_ref().map(|r| &r.best_move));
+        let mut best: Option<(Move, i32)> = None;
+        let mut alpha = -MATE - 1;
+        for m in moves {
+            let score = -search.negamax(&board.after_move(&m), depth - 1, -MATE - 1, -alpha, 1);
+            if search.stopped && depth > 1 {
+                break;
+            }
+            if best.is_none() || score > alpha {
+                alpha = score;
+                best = Some((m, score));
+            }
+        }
+        if search.stopped && depth > 1 {
+            break;
+        }
+        // Time may run out during the first iteration, which is finished anyway
+        search.stopped = false;
+        match best {
+            Some((best_move, score)) => {
+                result = Some(SearchResult {
+                    best_move,
+                    score,
+                    depth,
+                    nodes: search.nodes,
+                });
+                if score.abs() >= MATE - depth as i32 {
+                    break;
+                }
+            }
+            None => break,
+        }
+        if search.should_stop() {
+            break;
+        }
+    }
+    result
+}
+
+/// Whether the engine playing the given color agrees to a draw, which it does when it
+/// considers itself worse off.
+pub fn accepts_draw(board: &Board, engine_white: bool) -> bool {
+    match search(board, DRAW_OFFER_DEPTH, DRAW_OFFER_TIME) {
+        Some(result) => {
+            let engines_score = if is_figures_turn(engine_white, board.round) {
+                result.score
+            } else {
+                -result.score
+            };
+            engines_score < ACCEPT_DRAW_BELOW
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::square;
+
+    fn best_move(fen: &str, depth: u32) -> Move {
+        let board = Board::from_fen(fen).unwrap();
+        search(&board, depth, Duration::from_secs(60))
+            .unwrap()
+            .best_move
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        let m = best_move("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2);
+        assert_eq!(m.to, square("a8"));
+    }
+
+    #[test]
+    fn captures_hanging_queen() {
+        let m = best_move("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 2);
+        assert_eq!(m.to, square("d5"));
+    }
+
+    #[test]
+    fn avoids_losing_the_queen() {
+        let m = best_move("4k3/8/4p3/3p4/8/3Q4/8/4K3 w - - 0 1", 2);
+        assert_ne!(m.to, square("d5"));
+    }
+
+    #[test]
+    fn no_move_when_mated() {
+        let board = Board::from_fen("7k/8/8/8/8/8/8/K5RR b - - 3 40").unwrap();
+        assert_eq!(search(&board, 3, Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn accepts_draw_only_when_worse() {
+        assert!(!accepts_draw(&Board::init(), false));
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        assert!(accepts_draw(&board, false));
+        assert!(!accepts_draw(&board, true));
+    }
+
+    #[test]
+    fn evaluation_is_symmetric() {
+        let board = Board::init();
+        assert_eq!(board.evaluate(), 0);
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+        assert!(board.evaluate() < -800);
+    }
+}
@@ -0,0 +1,360 @@
+use crate::{is_figures_turn, Board, Figure, FigureType, Position};
+
+pub(crate) const START_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+impl FigureType {
+    fn from_fen_char(c: char) -> Option<FigureType> {
+        match c.to_ascii_lowercase() {
+            'p' => Some(FigureType::Pawn),
+            'n' => Some(FigureType::Knight),
+            'b' => Some(FigureType::Bishop),
+            'r' => Some(FigureType::Rook),
+            'q' => Some(FigureType::Queen),
+            'k' => Some(FigureType::King),
+            _ => None,
+        }
+    }
+
+    fn fen_char(&self, white: bool) -> char {
+        let c = match self {
+            FigureType::Pawn => 'p',
+            FigureType::Knight => 'n',
+            FigureType::Bishop => 'b',
+            FigureType::Rook => 'r',
+            FigureType::Queen => 'q',
+            FigureType::King => 'k',
+        };
+        if white {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        }
+    }
+}
+
+impl Position {
+    /// Parses a square like `e3` into a position.
+    pub fn from_square(square: &str) -> Option<Position> {
+        let mut chars = square.chars();
+        let file = chars.next()?;
+        let rank = chars.next()?;
+        if chars.next().is_some() || !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+            return None;
+        }
+        Some(Position::new(
+            file as i32 - 'a' as i32,
+            rank as i32 - '1' as i32,
+        ))
+    }
+
+    /// Name of the square, like `e4`.
+    pub fn to_square(self) -> String {
+        format!(
+            "{}{}",
+            (b'a' + self.x as u8) as char,
+            (b'1' + self.y as u8) as char
+        )
+    }
+}
+
+impl Board {
+    /// Board of a position in Forsyth-Edwards Notation.
+    pub fn from_fen(fen: &str) -> Result<Board, String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(format!("expected 6 FEN fields, found {}", fields.len()));
+        }
+
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(format!("expected 8 ranks, found {}", ranks.len()));
+        }
+        let mut figures = vec![];
+        for (i, rank) in ranks.iter().enumerate() {
+            let y = 7 - i as i32;
+            let mut x = 0;
+            for c in rank.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    x += empty as i32;
+                    continue;
+                }
+                let kind = FigureType::from_fen_char(c)
+                    .ok_or_else(|| format!("invalid piece '{}' in rank {}", c, y + 1))?;
+                let white = c.is_ascii_uppercase();
+                if kind == FigureType::Pawn && (y == 0 || y == 7) {
+                    return Err(format!("pawn on the last rank {}", y + 1));
+                }
+                let first_move = kind == FigureType::Pawn && y == if white { 1 } else { 6 };
+                figures.push(Figure::new(kind, Position::new(x, y), white, first_move));
+                x += 1;
+            }
+            if x != 8 {
+                return Err(format!("rank {} does not have 8 squares", y + 1));
+            }
+        }
+        for (white, color) in [(true, "white"), (false, "black")] {
+            let kings = figures
+                .iter()
+                .filter(|f| f.kind == FigureType::King && f.white == white)
+                .count();
+            if kings != 1 {
+                return Err(format!("expected one {} king, found {}", color, kings));
+            }
+        }
+
+        let round_offset = match fields[1] {
+            "w" => 0,
+            "b" => 1,
+            side => return Err(format!("invalid side to move '{}'", side)),
+        };
+
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                let (white, rook_x) = match c {
+                    'K' => (true, 7),
+                    'Q' => (true, 0),
+                    'k' => (false, 7),
+                    'q' => (false, 0),
+                    _ => return Err(format!("invalid castling right '{}'", c)),
+                };
+                let y = if white { 0 } else { 7 };
+                let mut castling = figures.iter_mut().filter(|f| {
+                    f.white == white
+                        && (f.kind == FigureType::Rook && f.position == Position::new(rook_x, y)
+                            || f.kind == FigureType::King && f.position == Position::new(4, y))
+                });
+                match (castling.next(), castling.next()) {
+                    (Some(king_or_rook), Some(other)) => {
+                        king_or_rook.first_move = true;
+                        other.first_move = true;
+                    }
+                    _ => {
+                        return Err(format!(
+                            "castling right '{}' without king and rook on their squares",
+                            c
+                        ))
+                    }
+                }
+            }
+        }
+
+        let en_passant = match fields[3] {
+            "-" => None,
+            square => {
+                let position = Position::from_square(square)
+                    .filter(|&position| {
+                        // The skipped square lies behind the pawn of the side that just moved
+                        let white_moved = round_offset == 1;
+                        let (y, pawn_y) = if white_moved { (2, 3) } else { (5, 4) };
+                        position.y == y
+                            && figures.iter().any(|f| {
+                                f.kind == FigureType::Pawn
+                                    && f.white == white_moved
+                                    && f.position == Position::new(position.x, pawn_y)
+                            })
+                            && figures.iter().all(|f| f.position != position)
+                    })
+                    .ok_or_else(|| format!("invalid en passant square '{}'", square))?;
+                Some(position)
+            }
+        };
+        let halfmove_clock = fields[4]
+            .parse::<u32>()
+            .map_err(|_| format!("invalid halfmove clock '{}'", fields[4]))?;
+        let round = fields[5]
+            .parse::<i32>()
+            .ok()
+            .filter(|&n| n >= 1)
+            .and_then(|n| (n - 1).checked_mul(2))
+            .and_then(|n| n.checked_add(round_offset))
+            .ok_or_else(|| format!("invalid fullmove number '{}'", fields[5]))?;
+
+        let mut board = Board::new(figures, round);
+        if board.is_in_check(round_offset == 1) {
+            return Err("the side not to move is in check".to_string());
+        }
+        board.en_passant = en_passant;
+        board.halfmove_clock = halfmove_clock;
+        board.hash = board.compute_hash();
+        board.positions = vec![board.hash];
+        board.status = board.compute_status();
+        Ok(board)
+    }
+
+    /// The position in Forsyth-Edwards Notation.
+    pub fn to_fen(&self) -> String {
+        let mut placement = vec![];
+        for y in (0..8).rev() {
+            let mut rank = String::new();
+            let mut empty = 0;
+            for x in 0..8 {
+                match self.occupied_by(Position::new(x, y)) {
+                    Some(figure) => {
+                        if empty > 0 {
+                            rank.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        rank.push(figure.kind.fen_char(figure.white));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+            placement.push(rank);
+        }
+
+        let side = if is_figures_turn(true, self.round) {
+            "w"
+        } else {
+            "b"
+        };
+
+        let mut castling = String::new();
+        for (c, white, rook_x) in [
+            ('K', true, 7),
+            ('Q', true, 0),
+            ('k', false, 7),
+            ('q', false, 0),
+        ] {
+            if self.has_castling_right(white, rook_x) {
+                castling.push(c);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self
+            .en_passant
+            .map_or("-".to_string(), |position| position.to_square());
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement.join("/"),
+            side,
+            castling,
+            en_passant,
+            self.halfmove_clock,
+            self.round / 2 + 1
+        )
+    }
+
+    pub(crate) fn has_castling_right(&self, white: bool, rook_x: i32) -> bool {
+        let y = if white { 0 } else { 7 };
+        let unmoved = |kind: FigureType, position: Position| {
+            matches!(self.occupied_by(position),
+                Some(f) if f.kind == kind && f.white == white && f.first_move)
+        };
+        unmoved(FigureType::King, Position::new(4, y))
+            && unmoved(FigureType::Rook, Position::new(rook_x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameStatus;
+
+    #[test]
+    fn start_position_round_trip() {
+        assert_eq!(Board::init().to_fen(), START_POSITION);
+        let board = Board::from_fen(START_POSITION).unwrap();
+        assert_eq!(board.to_fen(), START_POSITION);
+        assert_eq!(board.figures.len(), 32);
+        assert_eq!(board.round, 0);
+    }
+
+    #[test]
+    fn fen_after_moves() {
+        let mut board = Board::init();
+        board.move_figure(Position::new(4, 1), Position::new(4, 3));
+        board.end_turn();
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+        board.move_figure(Position::new(6, 7), Position::new(5, 5));
+        board.end_turn();
+        board.move_figure(Position::new(4, 0), Position::new(4, 1));
+        board.end_turn();
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPPKPPP/RNBQ1BNR b kq - 2 2"
+        );
+    }
+
+    #[test]
+    fn load_position() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.to_fen(), fen);
+        let king = board.get_figure_from_position(Position::new(4, 0)).unwrap();
+        let options = king.get_legal_move_options(&board);
+        assert!(options.movable.contains(&Position::new(6, 0)));
+        assert!(options.movable.contains(&Position::new(2, 0)));
+
+        let fen = "7k/8/8/8/8/8/8/K5RR b - - 3 40";
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.round, 79);
+        assert_eq!(board.status, GameStatus::Checkmate);
+        assert_eq!(board.to_fen(), fen);
+    }
+
+    #[test]
+    fn invalid_fen() {
+        assert!(Board::from_fen("").is_err());
+        assert!(Board::from_fen("8/8/8/8/8/8/8 w - - 0 1").is_err());
+        assert!(Board::from_fen("8/8/8/8/8/8/8/7X w - - 0 1").is_err());
+        assert!(Board::from_fen("8/8/8/8/8/8/8/9 w - - 0 1").is_err());
+        assert!(Board::from_fen("8/8/8/8/8/8/8/8 x - - 0 1").is_err());
+        assert!(Board::from_fen("8/8/8/8/8/8/8/8 w - e9 0 1").is_err());
+        assert!(Board::from_fen("8/8/8/8/8/8/8/8 w - - 0 0").is_err());
+    }
+
+    #[test]
+    fn fullmove_number_overflow() {
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 2000000000").is_err());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1073741825").is_err());
+    }
+
+    #[test]
+    fn missing_king() {
+        assert!(Board::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").is_err());
+        assert!(Board::from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn several_kings() {
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/K3K3 w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn pawn_on_last_rank() {
+        assert!(Board::from_fen("P3k3/8/8/8/8/8/8/4K3 w - - 0 1").is_err());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/p3K3 w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn en_passant_without_pawn() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2";
+        assert!(Board::from_fen(fen).is_ok());
+        assert!(Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - e5 0 2").is_err());
+        assert!(Board::from_fen("4k3/8/8/4P3/8/8/8/4K3 w - d6 0 2").is_err());
+        assert!(Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 b - d6 0 2").is_err());
+    }
+
+    #[test]
+    fn side_not_to_move_in_check() {
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K2R b - - 0 1").is_ok());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4R2K w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn castling_right_without_rook() {
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1").is_err());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/3K3R w K - 0 1").is_err());
+        assert!(Board::from_fen("4k2r/8/8/8/8/8/8/4K3 w q - 0 1").is_err());
+    }
+}
@@ -0,0 +1,245 @@
+//! Moves played in a game, which can be taken back and replayed.
+use crate::{Board, Figure, FigureType, Position};
+use serde::Serialize;
+
+/// A move as played, with the board state it replaced so it can be taken back.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PlayedMove {
+    /// Position of the moved piece before the move
+    pub from: Position,
+    /// Position of the moved piece after the move
+    pub to: Position,
+    /// Kind the pawn was promoted to
+    pub promotion: Option<FigureType>,
+    /// Standard algebraic notation of the move, like `Nxf3+`
+    pub san: String,
+    /// Index into `Board::figures` of the captured figure
+    captured: Option<usize>,
+    first_move: bool,
+    round: i32,
+    en_passant: Option<Position>,
+    halfmove_clock: u32,
+    /// Number of positions in `Board::positions` before the move
+    positions: usize,
+    hash: u64,
+}
+
+impl PlayedMove {
+    /// Moves the figure on the board and remembers what is needed to take the move back.
+    fn apply(board: &mut Board, from: Position, to: Position) -> PlayedMove {
+        let mut played = PlayedMove {
+            from,
+            to,
+            promotion: None,
+            san: board.san(from, to),
+            captured: None,
+            first_move: board
+                .get_figure_from_position(from)
+                .is_some_and(|figure| figure.first_move),
+            round: board.round,
+            en_passant: board.en_passant,
+            halfmove_clock: board.halfmove_clock,
+            positions: board.positions.len(),
+            hash: board.hash,
+        };
+        played.captured = board.move_figure(from, to);
+        played
+    }
+
+    /// The figure the move captured, as it lies on a board the move was played on.
+    pub fn captured<'a>(&self, board: &'a Board) -> Option<&'a Figure> {
+        self.captured.map(|i| &board.figures[i])
+    }
+}
+
+/// Moves played since the position given by `start_fen`.
+#[derive(Serialize, Clone, Debug)]
+pub struct History {
+    /// Position the game started from
+    pub start_fen: String,
+    /// Moves played, the last one last
+    pub moves: Vec<PlayedMove>,
+    /// Taken back moves, the next one to redo last
+    undone: Vec<PlayedMove>,
+}
+
+impl History {
+    /// Empty history starting from the board's position.
+    pub fn new(board: &Board) -> History {
+        History {
+            start_fen: board.to_fen(),
+            moves: vec![],
+            undone: vec![],
+        }
+    }
+
+    /// Plays a legal move on the board and records it. A pawn reaching the last rank waits
+    /// for promotion unless a kind is given.
+    pub fn play(
+        &mut self,
+        board: &mut Board,
+        from: Position,
+        to: Position,
+        promotion: Option<FigureType>,
+    ) {
+        let mut played = PlayedMove::apply(board, from, to);
+        if board.reached_last_rank(to) {
+            board.pending_promotion = Some(to);
+        } else {
+            board.end_turn();
+            played.san.push_str(board.status.san_suffix());
+        }
+        self.moves.push(played);
+        self.undone.clear();
+        if let Some(kind) = promotion {
+            self.promote(board, kind);
+        }
+    }
+
+    /// Promotes the pawn of the last move and completes its SAN.
+    pub fn promote(&mut self, board: &mut Board, kind: FigureType) -> bool {
+        if !board.promote(kind.clone()) {
+            return false;
+        }
+        if let Some(played) = self.moves.last_mut() {
+            played.san.push('=');
+            played.san.push_str(kind.san_letter());
+            played.san.push_str(board.status.san_suffix());
+            played.promotion = Some(kind);
+        }
+        true
+    }
+
+    /// Takes back the last move, returning false if there is none.
+    pub fn undo(&mut self, board: &mut Board) -> bool {
+        match self.moves.pop() {
+            Some(played) => {
+                board.take_back(&played);
+                self.undone.push(played);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Plays the last move taken back again, returning false if there is none.
+    pub fn redo(&mut self, board: &mut Board) -> bool {
+        match self.undone.pop() {
+            Some(played) => {
+                let undone = std::mem::take(&mut self.undone);
+                self.play(board, played.from, played.to, played.promotion);
+                self.undone = undone;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Board {
+    /// Restores the board as it was before the move, reviving a captured figure.
+    fn take_back(&mut self, played: &PlayedMove) {
+        if let Some(figure) = self.get_figure_from_position_mut(played.to) {
+            figure.position = played.from;
+            figure.first_move = played.first_move;
+            if played.promotion.is_some() {
+                figure.kind = FigureType::Pawn;
+            }
+            if figure.kind == FigureType::King && (played.to.x - played.from.x).abs() == 2 {
+                let (rook_x, rook_target_x) = if played.to.x > played.from.x {
+                    (played.to.x - 1, 7)
+                } else {
+                    (played.to.x + 1, 0)
+                };
+                let y = played.from.y;
+                if let Some(rook) = self.get_figure_from_position_mut(Position::new(rook_x, y)) {
+                    rook.position.x = rook_target_x;
+                    rook.first_move = true;
+                }
+            }
+        }
+        if let Some(i) = played.captured {
+            self.figures[i].alive = true;
+        }
+        self.update_bitboards();
+        self.round = played.round;
+        self.en_passant = played.en_passant;
+        self.halfmove_clock = played.halfmove_clock;
+        self.positions.truncate(played.positions);
+        self.hash = played.hash;
+        self.pending_promotion = None;
+        self.status = self.compute_status();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::from_pgn;
+    use crate::test_util::{play, square};
+
+    #[test]
+    fn undo_and_redo_restore_board() {
+        let pgn = "1. e4 d5 2. exd5 Nf6 3. Bb5+ c6 4. dxc6 Qa5 5. cxb7+ Nbd7 6. bxa8=Q e6 \
+                   7. Nf3 Bc5 8. O-O";
+        let (mut board, mut history) = from_pgn(pgn).unwrap();
+        let mut fens = vec![board.to_fen()];
+        while history.undo(&mut board) {
+            fens.push(board.to_fen());
+        }
+        assert_eq!(board.to_fen(), Board::init().to_fen());
+        assert_eq!(board.figures.iter().filter(|f| f.alive).count(), 32);
+
+        while history.redo(&mut board) {
+            fens.pop();
+            assert_eq!(&board.to_fen(), fens.last().unwrap());
+        }
+        assert_eq!(history.moves.len(), 15);
+        assert_eq!(history.moves[14].san, "O-O");
+    }
+
+    #[test]
+    fn undo_en_passant_and_promotion() {
+        let fen = "3k4/1P6/8/8/5p2/8/4P3/4K3 w - - 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut history = History::new(&board);
+        play(&mut board, &mut history, "e2", "e4");
+        play(&mut board, &mut history, "f4", "e3");
+        assert_eq!(board.alive_figures().count(), 4);
+        let captured = history.moves[1].captured(&board).unwrap();
+        assert_eq!(captured.kind, FigureType::Pawn);
+        assert_eq!(captured.position, square("e4"));
+        assert!(history.moves[0].captured(&board).is_none());
+        history.play(
+            &mut board,
+            square("b7"),
+            square("b8"),
+            Some(FigureType::Queen),
+        );
+        assert_eq!(history.moves[2].san, "b8=Q+");
+
+        history.undo(&mut board);
+        history.undo(&mut board);
+        assert_eq!(board.en_passant, Some(square("e3")));
+        assert_eq!(board.figures.iter().filter(|f| f.alive).count(), 5);
+        history.undo(&mut board);
+        assert_eq!(board.to_fen(), fen);
+        assert!(!history.undo(&mut board));
+
+        history.redo(&mut board);
+        play(&mut board, &mut history, "d8", "d7");
+        assert!(!history.redo(&mut board));
+    }
+
+    #[test]
+    fn undo_pending_promotion() {
+        let fen = "3k4/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut history = History::new(&board);
+        play(&mut board, &mut history, "b7", "b8");
+        assert_eq!(board.pending_promotion, Some(square("b8")));
+        history.undo(&mut board);
+        assert_eq!(board.pending_promotion, None);
+        assert_eq!(board.to_fen(), fen);
+    }
+}
@@ -0,0 +1,864 @@
+//! Chess rules without any user interface: the board with its legal moves and game status,
+//! move history, FEN and PGN, clocks and a built-in engine.
+//!
+//! ```
+//! use chess_core::history::History;
+//! use chess_core::{Board, GameStatus};
+//!
+//! let mut board = Board::init();
+//! let mut history = History::new(&board);
+//! for san in ["f3", "e5", "g4", "Qh4#"] {
+//!     let (from, to, promotion) = board.parse_san(san).unwrap();
+//!     history.play(&mut board, from, to, promotion);
+//! }
+//! assert_eq!(board.status, GameStatus::Checkmate);
+//! ```
+#![warn(missing_docs)]
+use bitboard::Bitboards;
+use draw::DrawReason;
+use serde::{Deserialize, Serialize};
+
+mod bitboard;
+pub mod clock;
+pub mod draw;
+pub mod engine;
+mod fen;
+pub mod history;
+pub mod perft;
+pub mod pgn;
+#[cfg(test)]
+mod test_util;
+mod zobrist;
+
+/// Whether it is the turn of the given color in the given round, white moving in even rounds.
+pub fn is_figures_turn(white: bool, round: i32) -> bool {
+    white == (round % 2 == 0)
+}
+
+/// Kind of a chess piece, serialized by its name like `"Knight"` for the frontend.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum FigureType {
+    /// Moves one square forward, two from its start rank, and captures diagonally
+    Pawn,
+    /// Moves one square in any direction, or two when castling
+    King,
+    /// Slides any number of squares straight or diagonally
+    Queen,
+    /// Slides any number of squares diagonally
+    Bishop,
+    /// Jumps in an L shape over other pieces
+    Knight,
+    /// Slides any number of squares along ranks and files
+    Rook,
+}
+
+impl Figure {
+    /// Pseudo-legal moves of `get_move_options` without those leaving the own king in check.
+    pub fn get_legal_move_options(&self, board: &Board) -> MoveOptions {
+        let mut options = self.get_move_options(board).remove_out_of_bounds_options();
+        options
+            .movable
+            .retain(|&p| !board.move_leaves_king_in_check(self.position, p));
+        options
+            .killable
+            .retain(|&p| !board.move_leaves_king_in_check(self.position, p));
+        options
+    }
+    /// Target positions of the king if castling king-side or queen-side is possible.
+    fn castling_options(&self, board: &Board) -> Vec<Position> {
+        let mut movable = vec![];
+        if !self.first_move || board.is_attacked(self.position, !self.white) {
+            return movable;
+        }
+        for (rook_x, direction) in [(0, -1), (7, 1)] {
+            match board.occupied_by(Position::new(rook_x, self.position.y)) {
+                Some(rook)
+                    if rook.kind == FigureType::Rook
+                        && rook.white == self.white
+                        && rook.first_move => {}
+                _ => continue,
+            }
+            let (low, high) = if rook_x < self.position.x {
+                (rook_x + 1, self.position.x)
+            } else {
+                (self.position.x + 1, rook_x)
+            };
+            if (low..high).any(|x| {
+                board
+                    .occupied_by(Position::new(x, self.position.y))
+                    .is_some()
+            }) {
+                continue;
+            }
+            // King may neither pass through nor land on an attacked position
+            let passing =
+                [1, 2].map(|d| Position::new(self.position.x + d * direction, self.position.y));
+            if passing.iter().any(|&p| board.is_attacked(p, !self.white)) {
+                continue;
+            }
+            movable.push(passing[1]);
+        }
+        movable
+    }
+    fn set_position(&mut self, x: i32, y: i32) {
+        self.position.x = x;
+        self.position.y = y;
+        self.first_move = false;
+    }
+    fn get_move_options(&self, board: &Board) -> MoveOptions {
+        let square = match bitboard::square(self.position) {
+            Some(square) => square,
+            None => {
+                return MoveOptions {
+                    movable: vec![],
+                    killable: vec![],
+                }
+            }
+        };
+        let own = board.bitboards.color(self.white);
+        let enemy = board.bitboards.color(!self.white);
+        let occupied = own | enemy;
+        let attacks = bitboard::attacks(&self.kind, self.white, square, occupied);
+        let (movable, killable) = match &self.kind {
+            FigureType::Pawn => {
+                let direction = if self.white { 1 } else { -1 };
+                let mut movable = 0;
+                // Movable position if free in front
+                let p = Position::new(self.position.x, self.position.y + direction);
+                if bitboard::bit(p) & occupied == 0 {
+                    movable |= bitboard::bit(p);
+                    // Movable position if free in front and first move
+                    let p = Position::new(self.position.x, self.position.y + 2 * direction);
+                    if self.first_move && bitboard::bit(p) & occupied == 0 {
+                        movable |= bitboard::bit(p);
+                    }
+                }
+                let mut killable = attacks & enemy;
+                // Killable position behind a pawn that just moved two steps
+                if let Some(target) = board.en_passant {
+                    let passed = Position::new(target.x, self.position.y);
+                    if attacks & bitboard::bit(target) != 0
+                        && matches!(board.occupied_by(passed),
+                            Some(f) if f.kind == FigureType::Pawn && f.white != self.white)
+                    {
+                        killable |= bitboard::bit(target);
+                    }
+                }
+                (movable, killable)
+            }
+            _ => (attacks & !occupied, attacks & enemy),
+        };
+        let mut movable = bitboard::positions(movable);
+        if self.kind == FigureType::King {
+            movable.extend(self.castling_options(board));
+        }
+        MoveOptions {
+            movable,
+            killable: bitboard::positions(killable),
+        }
+    }
+}
+
+/// A piece on the board, or a captured one.
+#[derive(Serialize, Clone, Debug)]
+pub struct Figure {
+    /// Kind of the piece, which changes on promotion
+    pub kind: FigureType,
+    /// Current position, or the last one of a captured piece
+    pub position: Position,
+    /// Color of the piece
+    pub white: bool,
+    /// False once the piece was captured
+    pub alive: bool,
+    first_move: bool,
+}
+
+impl Figure {
+    pub(crate) fn new(
+        kind: FigureType,
+        position: Position,
+        white: bool,
+        first_move: bool,
+    ) -> Figure {
+        Figure {
+            kind,
+            position,
+            white,
+            alive: true,
+            first_move,
+        }
+    }
+}
+
+/// A move from one position to another, with the kind a pawn promotes to on the last rank.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Move {
+    /// Position of the moving piece
+    pub from: Position,
+    /// Target position, for castling the one of the king
+    pub to: Position,
+    /// New kind of a pawn reaching the last rank
+    pub promotion: Option<FigureType>,
+}
+
+impl Move {
+    /// Long algebraic notation as used by UCI, like `e2e4` or `e7e8q`.
+    pub fn to_uci(&self) -> String {
+        let promotion = self
+            .promotion
+            .as_ref()
+            .map_or(String::new(), |kind| kind.san_letter().to_lowercase());
+        format!(
+            "{}{}{}",
+            self.from.to_square(),
+            self.to.to_square(),
+            promotion
+        )
+    }
+
+    /// Parses long algebraic notation like `e7e8q` without checking that the move is legal.
+    pub fn from_uci(uci: &str) -> Option<Move> {
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return None;
+        }
+        let promotion = match uci[4..].chars().next() {
+            Some(c) if c.is_ascii_lowercase() => {
+                Some(FigureType::from_san_letter(c.to_ascii_uppercase())?)
+            }
+            Some(_) => return None,
+            None => None,
+        };
+        Some(Move {
+            from: Position::from_square(&uci[..2])?,
+            to: Position::from_square(&uci[2..4])?,
+            promotion,
+        })
+    }
+}
+
+/// Target positions of a piece.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MoveOptions {
+    /// Empty positions the piece may move to
+    pub movable: Vec<Position>,
+    /// Positions the piece may capture on
+    pub killable: Vec<Position>,
+}
+
+impl MoveOptions {
+    fn remove_out_of_bounds_options(mut self) -> Self {
+        let allowed_range = 0..8;
+        self.movable.retain(|position| {
+            allowed_range.contains(&position.x) && allowed_range.contains(&position.y)
+        });
+        self
+    }
+}
+
+/// A square of the board, from (0, 0) for a1 to (7, 7) for h8.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Copy)]
+pub struct Position {
+    /// File, 0 for the a-file
+    pub x: i32,
+    /// Rank, 0 for the first rank
+    pub y: i32,
+}
+
+impl Position {
+    /// Position of the given file and rank, which may lie outside the board.
+    pub fn new(x: i32, y: i32) -> Position {
+        Position { x, y }
+    }
+}
+
+/// Status of the game from the view of the side to move.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum GameStatus {
+    /// The side to move may move freely
+    Ongoing,
+    /// The side to move is in check
+    Check,
+    /// The side to move is checkmated and lost
+    Checkmate,
+    /// The side to move has no legal move but is not in check
+    Stalemate,
+    /// The side to move ran out of time
+    Timeout,
+    /// The game ended in a draw
+    Draw(DrawReason),
+    /// A player gave up
+    Resignation {
+        /// Color of the player who resigned
+        white: bool,
+    },
+}
+
+impl GameStatus {
+    /// Whether the game is over.
+    pub fn is_decided(&self) -> bool {
+        matches!(
+            self,
+            GameStatus::Checkmate
+                | GameStatus::Stalemate
+                | GameStatus::Timeout
+                | GameStatus::Draw(_)
+                | GameStatus::Resignation { .. }
+        )
+    }
+}
+
+/// A chess position with everything needed to continue the game from it.
+#[derive(Serialize, Clone, Debug)]
+pub struct Board {
+    figures: Vec<Figure>,
+    /// Halfmoves played since the start of the game, white moves in even rounds
+    pub round: i32,
+    /// Status after the last move
+    pub status: GameStatus,
+    /// Position skipped by a pawn's double step in the last move
+    en_passant: Option<Position>,
+    /// Pawn on the last rank waiting for the player to choose its new kind
+    pub pending_promotion: Option<Position>,
+    /// Moves since the last capture or pawn move
+    halfmove_clock: u32,
+    /// Zobrist hash of the position, updated with every move
+    #[serde(skip)]
+    hash: u64,
+    /// Hashes of the positions since the start of the game, the current one last
+    #[serde(skip)]
+    positions: Vec<u64>,
+    #[serde(skip)]
+    bitboards: Bitboards,
+}
+
+impl Board {
+    fn position_threatened_from(&self, figure: &Figure) -> Vec<Position> {
+        bitboard::positions(self.attackers(figure.position, !figure.white))
+    }
+
+    fn king(&self, white: bool) -> Option<&Figure> {
+        let kings = self.bitboards.kind(&FigureType::King) & self.bitboards.color(white);
+        bitboard::positions(kings)
+            .first()
+            .and_then(|&position| self.get_figure_from_position(position))
+    }
+
+    /// Whether the king of the given color is attacked.
+    pub fn is_in_check(&self, white: bool) -> bool {
+        match self.king(white) {
+            Some(king) => !self.position_threatened_from(king).is_empty(),
+            None => false,
+        }
+    }
+
+    /// All legal moves of the side to move, with one move per promotion kind.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let white = is_figures_turn(true, self.round);
+        let mut moves = vec![];
+        for figure in self
+            .figures
+            .iter()
+            .filter(|figure| figure.white == white && figure.alive)
+        {
+            let options = figure.get_legal_move_options(self);
+            for to in options.movable.into_iter().chain(options.killable) {
+                if figure.kind == FigureType::Pawn && (to.y == 0 || to.y == 7) {
+                    for kind in [
+                        FigureType::Queen,
+                        FigureType::Rook,
+                        FigureType::Bishop,
+                        FigureType::Knight,
+                    ] {
+                        moves.push(Move {
+                            from: figure.position,
+                            to,
+                            promotion: Some(kind),
+                        });
+                    }
+                } else {
+                    moves.push(Move {
+                        from: figure.position,
+                        to,
+                        promotion: None,
+                    });
+                }
+            }
+        }
+        moves
+    }
+
+    /// Copy of the board without the hashes of earlier positions, for trying out moves.
+    fn scratch_copy(&self) -> Board {
+        Board {
+            figures: self.figures.clone(),
+            positions: vec![],
+            bitboards: self.bitboards.clone(),
+            ..*self
+        }
+    }
+
+    /// Board after the move without updating the status or the position history, for searching
+    /// through positions.
+    fn after_move(&self, m: &Move) -> Board {
+        let mut board = self.scratch_copy();
+        board.move_figure(m.from, m.to);
+        if let Some(kind) = &m.promotion {
+            board.change_kind(m.to, kind.clone());
+        }
+        board.round += 1;
+        board.hash ^= zobrist::BLACK_TO_MOVE;
+        board
+    }
+
+    fn has_legal_moves(&self, white: bool) -> bool {
+        self.figures
+            .iter()
+            .filter(|figure| figure.white == white && figure.alive)
+            .any(|figure| {
+                let options = figure.get_legal_move_options(self);
+                !options.movable.is_empty() || !options.killable.is_empty()
+            })
+    }
+
+    /// Status of the side whose turn it is according to `round`.
+    fn compute_status(&self) -> GameStatus {
+        let white = is_figures_turn(true, self.round);
+        let check = self.is_in_check(white);
+        if !self.has_legal_moves(white) {
+            return if check {
+                GameStatus::Checkmate
+            } else {
+                GameStatus::Stalemate
+            };
+        }
+        match self.automatic_draw() {
+            Some(reason) => GameStatus::Draw(reason),
+            None if check => GameStatus::Check,
+            None => GameStatus::Ongoing,
+        }
+    }
+
+    /// Whether a figure of the given color could capture on the position, regardless of
+    /// what stands there.
+    fn is_attacked(&self, position: Position, by_white: bool) -> bool {
+        self.attackers(position, by_white) != 0
+    }
+
+    fn end_turn(&mut self) {
+        self.round += 1;
+        self.hash ^= zobrist::BLACK_TO_MOVE;
+        self.positions.push(self.hash);
+        self.status = self.compute_status();
+    }
+
+    fn reached_last_rank(&self, position: Position) -> bool {
+        match self.get_figure_from_position(position) {
+            Some(figure) if figure.kind == FigureType::Pawn => {
+                position.y == if figure.white { 7 } else { 0 }
+            }
+            _ => false,
+        }
+    }
+
+    /// Replaces the pawn waiting for promotion and passes the turn.
+    fn promote(&mut self, kind: FigureType) -> bool {
+        if matches!(kind, FigureType::Pawn | FigureType::King) {
+            return false;
+        }
+        match self.pending_promotion.take() {
+            Some(position) => {
+                self.change_kind(position, kind);
+                self.end_turn();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Turns the figure on the position into another kind, for promotions.
+    fn change_kind(&mut self, position: Position, kind: FigureType) {
+        if let Some(i) = self.bitboards.figure_at(position) {
+            self.hash ^= self.figures[i].key();
+            self.figures[i].kind = kind;
+            self.hash ^= self.figures[i].key();
+            self.update_bitboards();
+        }
+    }
+
+    /// Moves the figure and returns the index of the captured figure, if any.
+    fn move_figure(&mut self, from: Position, to: Position) -> Option<usize> {
+        let mover = self.bitboards.figure_at(from)?;
+        let white = self.figures[mover].white;
+        // Castling rights and en passant are xored out here and back in once the move is done
+        self.hash ^= self.castling_key() ^ self.en_passant_key(white);
+        let castling = matches!(
+            self.get_figure_from_position(from),
+            Some(figure) if figure.kind == FigureType::King && (to.x - from.x).abs() == 2
+        );
+        let pawn = matches!(
+            self.get_figure_from_position(from),
+            Some(figure) if figure.kind == FigureType::Pawn
+        );
+        // En passant, the captured pawn is not on the target position
+        let captured_position = if pawn && from.x != to.x && self.occupied_by(to).is_none() {
+            Position::new(to.x, from.y)
+        } else {
+            to
+        };
+        let captured = self.bitboards.figure_at(captured_position);
+        if pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        self.en_passant = if pawn && (to.y - from.y).abs() == 2 {
+            Some(Position::new(from.x, (from.y + to.y) / 2))
+        } else {
+            None
+        };
+        if let Some(i) = captured {
+            self.hash ^= self.figures[i].key();
+            self.figures[i].alive = false;
+        }
+        self.hash ^= self.figures[mover].key();
+        self.figures[mover].set_position(to.x, to.y);
+        self.hash ^= self.figures[mover].key();
+        if castling {
+            let (rook_x, rook_target_x) = if to.x > from.x {
+                (7, to.x - 1)
+            } else {
+                (0, to.x + 1)
+            };
+            if let Some(i) = self.bitboards.figure_at(Position::new(rook_x, from.y)) {
+                self.hash ^= self.figures[i].key();
+                self.figures[i].set_position(rook_target_x, from.y);
+                self.hash ^= self.figures[i].key();
+            }
+        }
+        self.update_bitboards();
+        self.hash ^= self.castling_key() ^ self.en_passant_key(!white);
+        captured
+    }
+
+    /// Simulates the move on a copy of the board and checks if the mover's king is attacked afterwards.
+    fn move_leaves_king_in_check(&self, from: Position, to: Position) -> bool {
+        let white = match self.get_figure_from_position(from) {
+            Some(figure) => figure.white,
+            None => return false,
+        };
+        let mut board = self.scratch_copy();
+        board.move_figure(from, to);
+        board.is_in_check(white)
+    }
+
+    fn occupied_by(&self, position: Position) -> Option<&Figure> {
+        self.get_figure_from_position(position)
+    }
+
+    /// The standard starting position.
+    pub fn init() -> Board {
+        let mut fig: Vec<Figure> = vec![
+            Figure::new(FigureType::Rook, Position::new(0, 0), true, true),
+            Figure::new(FigureType::Knight, Position::new(1, 0), true, true),
+            Figure::new(FigureType::Bishop, Position::new(2, 0), true, true),
+            Figure::new(FigureType::Queen, Position::new(3, 0), true, true),
+            Figure::new(FigureType::King, Position::new(4, 0), true, true),
+            Figure::new(FigureType::Bishop, Position::new(5, 0), true, true),
+            Figure::new(FigureType::Knight, Position::new(6, 0), true, true),
+            Figure::new(FigureType::Rook, Position::new(7, 0), true, true),
+            Figure::new(FigureType::Rook, Position::new(0, 7), false, true),
+            Figure::new(FigureType::Knight, Position::new(1, 7), false, true),
+            Figure::new(FigureType::Bishop, Position::new(2, 7), false, true),
+            Figure::new(FigureType::Queen, Position::new(3, 7), false, true),
+            Figure::new(FigureType::King, Position::new(4, 7), false, true),
+            Figure::new(FigureType::Bishop, Position::new(5, 7), false, true),
+            Figure::new(FigureType::Knight, Position::new(6, 7), false, true),
+            Figure::new(FigureType::Rook, Position::new(7, 7), false, true),
+        ];
+        for i in 0..8 {
+            fig.push(Figure::new(
+                FigureType::Pawn,
+                Position::new(i, 1),
+                true,
+                true,
+            ));
+            fig.push(Figure::new(
+                FigureType::Pawn,
+                Position::new(i, 6),
+                false,
+                true,
+            ));
+        }
+        Board::new(fig, 0)
+    }
+
+    fn new(figures: Vec<Figure>, round: i32) -> Board {
+        let mut board = Board {
+            figures,
+            round,
+            status: GameStatus::Ongoing,
+            en_passant: None,
+            pending_promotion: None,
+            halfmove_clock: 0,
+            hash: 0,
+            positions: vec![],
+            bitboards: Bitboards::default(),
+        };
+        board.update_bitboards();
+        board.hash = board.compute_hash();
+        board.positions.push(board.hash);
+        board.status = board.compute_status();
+        board
+    }
+
+    fn get_figure_from_position_mut(&mut self, position: Position) -> Option<&mut Figure> {
+        let i = self.bitboards.figure_at(position)?;
+        Some(&mut self.figures[i])
+    }
+
+    /// The piece standing on the position.
+    pub fn get_figure_from_position(&self, position: Position) -> Option<&Figure> {
+        let i = self.bitboards.figure_at(position)?;
+        Some(&self.figures[i])
+    }
+
+    /// The pieces not captured yet.
+    pub fn alive_figures(&self) -> impl Iterator<Item = &Figure> {
+        self.figures.iter().filter(|figure| figure.alive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::play;
+
+    #[test]
+    fn test_is_check() {
+        let queen = Figure::new(FigureType::Queen, Position::new(4, 4), true, true);
+        let king = Figure::new(FigureType::King, Position::new(6, 4), false, true);
+        let board = Board::new(vec![queen.clone(), king.clone()], 0);
+        assert_eq!(board.position_threatened_from(&king), vec![queen.position]);
+
+        let board = Board::new(vec![king.clone()], 0);
+        assert_eq!(board.position_threatened_from(&king), vec![]);
+
+        let queen = Figure::new(FigureType::Queen, Position::new(4, 4), false, true);
+        let king = Figure::new(FigureType::King, Position::new(6, 4), false, true);
+        let board = Board::new(vec![queen.clone(), king.clone()], 0);
+        assert_eq!(board.position_threatened_from(&king), vec![]);
+    }
+
+    #[test]
+    fn pawn_test() {
+        let pawn = Figure::new(FigureType::Pawn, Position::new(4, 4), true, true);
+        let raw_options = MoveOptions {
+            movable: vec![Position::new(4, 5), Position::new(4, 6)],
+            killable: vec![],
+        };
+        assert_eq!(pawn.get_move_options(&Board::new(vec![], 0)), raw_options);
+        let pawn = Figure::new(FigureType::Pawn, Position::new(4, 4), true, false);
+        let raw_options = MoveOptions {
+            movable: vec![Position::new(4, 5)],
+            killable: vec![],
+        };
+        assert_eq!(pawn.get_move_options(&Board::new(vec![], 1)), raw_options);
+    }
+
+    #[test]
+    fn tried_moves_leave_history_behind() {
+        let mut board = Board::init();
+        let mut history = history::History::new(&board);
+        play(&mut board, &mut history, "g1", "f3");
+        assert_eq!(board.positions.len(), 2);
+        let m = &board.legal_moves()[0];
+        let after = board.after_move(m);
+        assert!(after.positions.is_empty());
+        assert_eq!(after.hash, after.compute_hash());
+        assert_eq!(board.positions.len(), 2);
+    }
+
+    #[test]
+    fn remove_out_of_bounds_position() {
+        let raw_options = MoveOptions {
+            movable: vec![
+                Position::new(-4, 5),
+                Position::new(-4, -5),
+                Position::new(4, -5),
+                Position::new(8, 5),
+                Position::new(5, 8),
+                Position::new(-4, 8),
+                Position::new(4, 5),
+                Position::new(0, 5),
+                Position::new(7, 5),
+                Position::new(5, 7),
+                Position::new(7, 7),
+            ],
+            killable: vec![],
+        };
+        let inbound_options = MoveOptions {
+            movable: vec![
+                Position::new(4, 5),
+                Position::new(0, 5),
+                Position::new(7, 5),
+                Position::new(5, 7),
+                Position::new(7, 7),
+            ],
+            killable: vec![],
+        };
+        assert_eq!(raw_options.remove_out_of_bounds_options(), inbound_options);
+    }
+
+    #[test]
+    fn pinned_figure_cannot_move() {
+        let king = Figure::new(FigureType::King, Position::new(4, 0), true, true);
+        let bishop = Figure::new(FigureType::Bishop, Position::new(4, 1), true, true);
+        let rook = Figure::new(FigureType::Rook, Position::new(4, 7), false, true);
+        let board = Board::new(vec![king, bishop.clone(), rook], 0);
+        assert_eq!(
+            bishop.get_legal_move_options(&board),
+            MoveOptions {
+                movable: vec![],
+                killable: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn king_cannot_move_into_check() {
+        let king = Figure::new(FigureType::King, Position::new(4, 0), true, false);
+        let rook = Figure::new(FigureType::Rook, Position::new(3, 7), false, true);
+        let pawn = Figure::new(FigureType::Pawn, Position::new(5, 1), false, false);
+        let board = Board::new(vec![king.clone(), rook, pawn], 0);
+        let options = king.get_legal_move_options(&board);
+        assert!(!options.movable.contains(&Position::new(3, 0)));
+        assert!(!options.movable.contains(&Position::new(3, 1)));
+        assert!(options.movable.contains(&Position::new(4, 1)));
+        assert_eq!(options.killable, vec![Position::new(5, 1)]);
+    }
+
+    #[test]
+    fn status_after_fools_mate() {
+        let mut board = Board::init();
+        board.move_figure(Position::new(5, 1), Position::new(5, 2));
+        board.move_figure(Position::new(4, 6), Position::new(4, 4));
+        board.move_figure(Position::new(6, 1), Position::new(6, 3));
+        board.move_figure(Position::new(3, 7), Position::new(7, 3));
+        board.round = 4;
+        assert_eq!(board.compute_status(), GameStatus::Checkmate);
+        assert!(board.compute_status().is_decided());
+    }
+
+    #[test]
+    fn status_check_and_stalemate() {
+        let king = Figure::new(FigureType::King, Position::new(0, 7), false, false);
+        let queen = Figure::new(FigureType::Queen, Position::new(1, 5), true, false);
+        let white_king = Figure::new(FigureType::King, Position::new(7, 0), true, false);
+        let board = Board::new(vec![king.clone(), queen, white_king.clone()], 1);
+        assert_eq!(board.status, GameStatus::Stalemate);
+
+        let queen = Figure::new(FigureType::Queen, Position::new(0, 3), true, false);
+        let board = Board::new(vec![king, queen, white_king], 1);
+        assert_eq!(board.status, GameStatus::Check);
+        assert_eq!(Board::init().status, GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn castling() {
+        let king = Figure::new(FigureType::King, Position::new(4, 0), true, true);
+        let rook_left = Figure::new(FigureType::Rook, Position::new(0, 0), true, true);
+        let rook_right = Figure::new(FigureType::Rook, Position::new(7, 0), true, true);
+        let black_king = Figure::new(FigureType::King, Position::new(4, 7), false, false);
+        let figures = vec![king.clone(), rook_left, rook_right, black_king];
+        let board = Board::new(figures.clone(), 0);
+        let options = king.get_legal_move_options(&board);
+        assert!(options.movable.contains(&Position::new(6, 0)));
+        assert!(options.movable.contains(&Position::new(2, 0)));
+
+        let mut board = board;
+        board.move_figure(Position::new(4, 0), Position::new(6, 0));
+        assert_eq!(
+            board.occupied_by(Position::new(5, 0)).unwrap().kind,
+            FigureType::Rook
+        );
+        assert!(board.occupied_by(Position::new(7, 0)).is_none());
+
+        // King would pass through the attacked position (5, 0)
+        let mut attacked = figures.clone();
+        attacked.push(Figure::new(
+            FigureType::Rook,
+            Position::new(5, 7),
+            false,
+            true,
+        ));
+        let board = Board::new(attacked, 0);
+        let options = king.get_legal_move_options(&board);
+        assert!(!options.movable.contains(&Position::new(6, 0)));
+        assert!(options.movable.contains(&Position::new(2, 0)));
+
+        // Queen-side blocked by a knight, king-side rook already moved
+        let mut blocked = figures;
+        blocked.push(Figure::new(
+            FigureType::Knight,
+            Position::new(1, 0),
+            true,
+            true,
+        ));
+        blocked[2].first_move = false;
+        let board = Board::new(blocked, 0);
+        let options = king.get_legal_move_options(&board);
+        assert!(!options.movable.contains(&Position::new(6, 0)));
+        assert!(!options.movable.contains(&Position::new(2, 0)));
+    }
+
+    #[test]
+    fn en_passant() {
+        let white_king = Figure::new(FigureType::King, Position::new(4, 0), true, false);
+        let black_king = Figure::new(FigureType::King, Position::new(4, 7), false, false);
+        let white_pawn = Figure::new(FigureType::Pawn, Position::new(4, 4), true, false);
+        let black_pawn = Figure::new(FigureType::Pawn, Position::new(3, 6), false, true);
+        let mut board = Board::new(vec![white_king, black_king, white_pawn, black_pawn], 1);
+        board.move_figure(Position::new(3, 6), Position::new(3, 4));
+        assert_eq!(board.en_passant, Some(Position::new(3, 5)));
+
+        let pawn = board.get_figure_from_position(Position::new(4, 4)).unwrap();
+        let options = pawn.get_legal_move_options(&board);
+        assert_eq!(options.killable, vec![Position::new(3, 5)]);
+
+        board.move_figure(Position::new(4, 4), Position::new(3, 5));
+        assert!(board.occupied_by(Position::new(3, 4)).is_none());
+        assert!(!board.figures[3].alive);
+        assert_eq!(board.en_passant, None);
+    }
+
+    #[test]
+    fn promotion() {
+        let white_king = Figure::new(FigureType::King, Position::new(4, 0), true, false);
+        let black_king = Figure::new(FigureType::King, Position::new(7, 5), false, false);
+        let pawn = Figure::new(FigureType::Pawn, Position::new(0, 6), true, false);
+        let mut board = Board::new(vec![white_king, black_king, pawn], 0);
+        board.move_figure(Position::new(0, 6), Position::new(0, 7));
+        assert!(board.reached_last_rank(Position::new(0, 7)));
+        board.pending_promotion = Some(Position::new(0, 7));
+
+        board.promote(FigureType::King);
+        assert_eq!(board.pending_promotion, Some(Position::new(0, 7)));
+        assert_eq!(board.round, 0);
+
+        board.promote(FigureType::Queen);
+        assert_eq!(board.pending_promotion, None);
+        assert_eq!(board.figures[2].kind, FigureType::Queen);
+        assert_eq!(board.round, 1);
+        assert_eq!(board.status, GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn is_turn_of() {
+        let white = true;
+        let black = false;
+        assert!(is_figures_turn(white, 0));
+        assert!(!is_figures_turn(black, 0));
+        assert!(!is_figures_turn(white, 1));
+        assert!(is_figures_turn(black, 1));
+    }
+}
@@ -0,0 +1,138 @@
+//! Move path enumeration, which counts the leaf nodes of the legal move tree to test move
+//! generation against known numbers.
+use crate::Board;
+use serde::Serialize;
+
+/// Leaf node counts per root move, as printed by the `divide` command of most engines.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Divide {
+    /// UCI notation of each root move with the leaf nodes below it
+    pub moves: Vec<(String, u64)>,
+    /// Leaf nodes of all root moves together
+    pub nodes: u64,
+}
+
+impl Board {
+    /// Number of leaf nodes of the legal move tree of the given depth.
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .iter()
+            .map(|m| self.after_move(m).perft(depth - 1))
+            .sum()
+    }
+
+    /// Like `perft`, but counted separately for each legal move.
+    pub fn divide(&self, depth: u32) -> Divide {
+        let mut moves: Vec<(String, u64)> = match depth {
+            0 => vec![],
+            _ => self
+                .legal_moves()
+                .iter()
+                .map(|m| (m.to_uci(), self.after_move(m).perft(depth - 1)))
+                .collect(),
+        };
+        moves.sort();
+        let nodes = if depth == 0 {
+            1
+        } else {
+            moves.iter().map(|(_, nodes)| nodes).sum()
+        };
+        Divide { moves, nodes }
+    }
+}
+
+// Positions and node counts from https://www.chessprogramming.org/Perft_Results
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_4_MIRRORED: &str =
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        let board = Board::from_fen(fen).unwrap();
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(
+                board.perft(depth as u32 + 1),
+                nodes,
+                "perft({}) of {}",
+                depth + 1,
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn start_position() {
+        let board = Board::init();
+        for (depth, nodes) in [1, 20, 400, 8902, 197281].into_iter().enumerate() {
+            assert_eq!(board.perft(depth as u32), nodes);
+        }
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(KIWIPETE, &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(POSITION_3, &[14, 191, 2812, 43238]);
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(POSITION_4, &[6, 264, 9467]);
+        assert_perft(POSITION_4_MIRRORED, &[6, 264, 9467]);
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(POSITION_5, &[44, 1486, 62379]);
+    }
+
+    #[test]
+    fn position_6() {
+        assert_perft(POSITION_6, &[46, 2079, 89890]);
+    }
+
+    #[test]
+    #[ignore = "takes minutes without optimizations, run with --release --ignored"]
+    fn deep_perft() {
+        assert_eq!(Board::init().perft(5), 4865609);
+        assert_perft(KIWIPETE, &[48, 2039, 97862, 4085603]);
+        assert_perft(POSITION_3, &[14, 191, 2812, 43238, 674624]);
+        assert_perft(POSITION_4, &[6, 264, 9467, 422333]);
+        assert_perft(POSITION_5, &[44, 1486, 62379, 2103487]);
+        assert_perft(POSITION_6, &[46, 2079, 89890, 3894594]);
+    }
+
+    #[test]
+    fn divide_start_position() {
+        let divide = Board::init().divide(2);
+        assert_eq!(divide.nodes, 400);
+        assert_eq!(divide.moves.len(), 20);
+        assert_eq!(divide.moves[0], ("a2a3".to_string(), 20));
+        assert!(divide.moves.contains(&("g1f3".to_string(), 20)));
+
+        let board = Board::from_fen("k7/4P3/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let divide = board.divide(1);
+        assert_eq!(divide.nodes, 7);
+        assert!(divide.moves.contains(&("e7e8q".to_string(), 1)));
+        assert!(divide.moves.contains(&("e7e8n".to_string(), 1)));
+        assert_eq!(Board::init().divide(0).nodes, 1);
+    }
+}
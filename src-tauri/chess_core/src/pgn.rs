@@ -0,0 +1,517 @@
+//! Portable Game Notation and Standard Algebraic Notation.
+use crate::draw::DrawReason;
+use crate::fen::START_POSITION;
+use crate::history::History;
+use crate::{is_figures_turn, Board, FigureType, GameStatus, Position};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LINE_WIDTH: usize = 80;
+
+impl History {
+    /// Exports the game as PGN with the Seven Tag Roster and SAN move text.
+    pub fn to_pgn(&self, board: &Board) -> String {
+        let result = game_result(board);
+        let mut pgn = String::new();
+        for (tag, value) in [
+            ("Event", "Tauri Chess game"),
+            ("Site", "?"),
+            ("Date", &today()),
+            ("Round", "-"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Result", result),
+        ] {
+            pgn.push_str(&format!("[{} \"{}\"]\n", tag, value));
+        }
+        if self.start_fen != START_POSITION {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", self.start_fen));
+        }
+        pgn.push('\n');
+
+        let start = Board::from_fen(&self.start_fen).map_or(0, |board| board.round);
+        let mut tokens = vec![];
+        for (i, played) in self.moves.iter().enumerate() {
+            let round = start + i as i32;
+            if is_figures_turn(true, round) {
+                tokens.push(format!("{}.", round / 2 + 1));
+            } else if i == 0 {
+                tokens.push(format!("{}...", round / 2 + 1));
+            }
+            tokens.push(played.san.clone());
+        }
+        if let Some(comment) = termination_comment(board) {
+            tokens.push(comment.to_string());
+        }
+        tokens.push(result.to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        pgn
+    }
+}
+
+/// Replays a PGN game from its starting position, which is either the standard one or given
+/// by the FEN tag. A result the moves do not reach themselves ends the game by resignation or
+/// agreed draw.
+pub fn from_pgn(pgn: &str) -> Result<(Board, History), String> {
+    let mut start_fen = START_POSITION.to_string();
+    let mut result = None;
+    let mut movetext = String::new();
+    for line in pgn.lines() {
+        let line = line.trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            let tag = &line[1..line.len() - 1];
+            if let Some((name, value)) = tag.split_once(char::is_whitespace) {
+                let value = value.trim().trim_matches('"');
+                match name {
+                    "FEN" => start_fen = value.to_string(),
+                    "Result" => result = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+        } else if !line.starts_with('%') {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    let mut board = Board::from_fen(&start_fen).map_err(|e| format!("invalid FEN tag: {}", e))?;
+    let mut history = History::new(&board);
+    let (tokens, termination) = movetext_tokens(&movetext)?;
+    for token in tokens {
+        let number = format!(
+            "{}{}",
+            board.round / 2 + 1,
+            if is_figures_turn(true, board.round) {
+                "."
+            } else {
+                "..."
+            }
+        );
+        if board.status.is_decided() {
+            return Err(format!("move {} {}: game is already over", number, token));
+        }
+        let (from, to, promotion) = board
+            .parse_san(&token)
+            .map_err(|e| format!("move {} {}: {}", number, token, e))?;
+        history.play(&mut board, from, to, promotion);
+    }
+    if !board.status.is_decided() {
+        board.status = match termination.or(result).as_deref() {
+            Some("1-0") => GameStatus::Resignation { white: false },
+            Some("0-1") => GameStatus::Resignation { white: true },
+            Some("1/2-1/2") => GameStatus::Draw(DrawReason::Agreement),
+            _ => board.status,
+        };
+    }
+    Ok((board, history))
+}
+
+/// SAN tokens of the movetext without move numbers, comments, variations and NAGs, and the
+/// result ending it.
+fn movetext_tokens(movetext: &str) -> Result<(Vec<String>, Option<String>), String> {
+    let mut cleaned = String::new();
+    let mut chars = movetext.chars();
+    let mut variation_depth = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                if !chars.by_ref().any(|c| c == '}') {
+                    return Err("unterminated comment".to_string());
+                }
+                cleaned.push(' ');
+            }
+            ';' => {
+                chars.by_ref().find(|&c| c == '\n');
+                cleaned.push(' ');
+            }
+            '(' => variation_depth += 1,
+            ')' if variation_depth > 0 => variation_depth -= 1,
+            ')' => return Err("unbalanced variation".to_string()),
+            _ if variation_depth > 0 => {}
+            _ => cleaned.push(c),
+        }
+    }
+    if variation_depth > 0 {
+        return Err("unterminated variation".to_string());
+    }
+
+    let mut tokens = vec![];
+    for token in cleaned.split_whitespace() {
+        if matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+            return Ok((tokens, Some(token.to_string())));
+        }
+        // Move numbers like `12.` or `12...`, possibly glued to the move as in `12.e4`, but
+        // not the digits of castling written as `0-0`
+        let number = token.trim_start_matches(|c: char| c.is_ascii_digit());
+        let token = match number.strip_prefix('.') {
+            Some(rest) if number.len() < token.len() => rest.trim_start_matches('.'),
+            _ => token,
+        };
+        if token.is_empty() || token.starts_with('$') {
+            continue;
+        }
+        tokens.push(token.to_string());
+    }
+    Ok((tokens, None))
+}
+
+fn game_result(board: &Board) -> &'static str {
+    match board.status {
+        GameStatus::Checkmate | GameStatus::Timeout if is_figures_turn(true, board.round) => "0-1",
+        GameStatus::Checkmate | GameStatus::Timeout => "1-0",
+        GameStatus::Stalemate | GameStatus::Draw(_) => "1/2-1/2",
+        GameStatus::Resignation { white: true } => "0-1",
+        GameStatus::Resignation { white: false } => "1-0",
+        _ => "*",
+    }
+}
+
+/// Comment after the last move for games the players ended themselves.
+fn termination_comment(board: &Board) -> Option<&'static str> {
+    match board.status {
+        GameStatus::Resignation { white: true } => Some("{White resigns}"),
+        GameStatus::Resignation { white: false } => Some("{Black resigns}"),
+        GameStatus::Draw(DrawReason::Agreement) => Some("{Draw agreed}"),
+        _ => None,
+    }
+}
+
+/// Current date as `YYYY.MM.DD`, computed from the days since the unix epoch.
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86400) as i64;
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+impl FigureType {
+    pub(crate) fn san_letter(&self) -> &'static str {
+        match self {
+            FigureType::Pawn => "",
+            FigureType::Knight => "N",
+            FigureType::Bishop => "B",
+            FigureType::Rook => "R",
+            FigureType::Queen => "Q",
+            FigureType::King => "K",
+        }
+    }
+}
+
+impl FigureType {
+    pub(crate) fn from_san_letter(c: char) -> Option<FigureType> {
+        match c {
+            'N' => Some(FigureType::Knight),
+            'B' => Some(FigureType::Bishop),
+            'R' => Some(FigureType::Rook),
+            'Q' => Some(FigureType::Queen),
+            'K' => Some(FigureType::King),
+            _ => None,
+        }
+    }
+}
+
+impl GameStatus {
+    pub(crate) fn san_suffix(&self) -> &'static str {
+        match self {
+            GameStatus::Check => "+",
+            GameStatus::Checkmate => "#",
+            _ => "",
+        }
+    }
+}
+
+impl Board {
+    /// Finds the single legal move of the side to move described by the SAN token.
+    pub fn parse_san(
+        &self,
+        token: &str,
+    ) -> Result<(Position, Position, Option<FigureType>), String> {
+        let white = is_figures_turn(true, self.round);
+        let san = token.trim_end_matches(['+', '#', '!', '?']);
+
+        if matches!(san, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+            let king = self.king(white).ok_or("no king to castle")?;
+            let direction = if san.len() == 3 { 1 } else { -1 };
+            let to = Position::new(king.position.x + 2 * direction, king.position.y);
+            if !king.castling_options(self).contains(&to)
+                || self.move_leaves_king_in_check(king.position, to)
+            {
+                return Err("illegal move".to_string());
+            }
+            return Ok((king.position, to, None));
+        }
+
+        let (san, promotion) = match san.split_once('=') {
+            Some((san, kind)) => {
+                let mut letters = kind.chars();
+                match (
+                    letters.next().and_then(FigureType::from_san_letter),
+                    letters.next(),
+                ) {
+                    (Some(kind), None) if kind != FigureType::King => (san, Some(kind)),
+                    _ => return Err("invalid promotion".to_string()),
+                }
+            }
+            None => match san.char_indices().last() {
+                Some((i, c)) if san.starts_with(|c: char| c.is_ascii_lowercase()) && i > 0 => {
+                    match FigureType::from_san_letter(c) {
+                        Some(kind) if kind != FigureType::King => (&san[..i], Some(kind)),
+                        _ => (san, None),
+                    }
+                }
+                _ => (san, None),
+            },
+        };
+
+        let (kind, rest) = match san.chars().next().and_then(FigureType::from_san_letter) {
+            Some(kind) => (kind, &san[1..]),
+            None => (FigureType::Pawn, san),
+        };
+        let rest: String = rest.chars().filter(|&c| c != 'x').collect();
+        if rest.len() < 2 || !rest.is_ascii() {
+            return Err("invalid move".to_string());
+        }
+        let (hint, square) = rest.split_at(rest.len() - 2);
+        let to = Position::from_square(square).ok_or("invalid target square")?;
+        let mut hint_file = None;
+        let mut hint_rank = None;
+        for c in hint.chars() {
+            match c {
+                'a'..='h' if hint_file.is_none() => hint_file = Some(c as i32 - 'a' as i32),
+                '1'..='8' if hint_rank.is_none() => hint_rank = Some(c as i32 - '1' as i32),
+                _ => return Err("invalid disambiguation".to_string()),
+            }
+        }
+
+        let candidates: Vec<Position> = self
+            .figures
+            .iter()
+            .filter(|f| f.alive && f.white == white && f.kind == kind)
+            .filter(|f| hint_file.is_none_or(|x| f.position.x == x))
+            .filter(|f| hint_rank.is_none_or(|y| f.position.y == y))
+            .filter(|f| {
+                let options = f.get_legal_move_options(self);
+                options.movable.contains(&to) || options.killable.contains(&to)
+            })
+            .map(|f| f.position)
+            .collect();
+        let from = match candidates[..] {
+            [from] => from,
+            [] => return Err("illegal move".to_string()),
+            _ => return Err("ambiguous move".to_string()),
+        };
+
+        let promotes = kind == FigureType::Pawn && to.y == if white { 7 } else { 0 };
+        match (promotes, &promotion) {
+            (true, None) => Err("missing promotion".to_string()),
+            (false, Some(_)) => Err("promotion is only possible on the last rank".to_string()),
+            _ => Ok((from, to, promotion)),
+        }
+    }
+
+    /// SAN of a legal move on this board, without promotion and check suffix which are only
+    /// known once the turn is over.
+    pub fn san(&self, from: Position, to: Position) -> String {
+        let figure = match self.get_figure_from_position(from) {
+            Some(figure) => figure,
+            None => return String::new(),
+        };
+        if figure.kind == FigureType::King && (to.x - from.x).abs() == 2 {
+            return if to.x > from.x { "O-O" } else { "O-O-O" }.to_string();
+        }
+        let capture =
+            self.occupied_by(to).is_some() || (figure.kind == FigureType::Pawn && from.x != to.x);
+        let mut san = figure.kind.san_letter().to_string();
+        if figure.kind == FigureType::Pawn {
+            if capture {
+                san.push_str(&from.to_square()[..1]);
+            }
+        } else {
+            let rivals: Vec<Position> = self
+                .figures
+                .iter()
+                .filter(|f| {
+                    f.alive
+                        && f.white == figure.white
+                        && f.kind == figure.kind
+                        && f.position != from
+                })
+                .filter(|f| {
+                    let options = f.get_legal_move_options(self);
+                    options.movable.contains(&to) || options.killable.contains(&to)
+                })
+                .map(|f| f.position)
+                .collect();
+            let square = from.to_square();
+            if !rivals.is_empty() {
+                if rivals.iter().all(|p| p.x != from.x) {
+                    san.push_str(&square[..1]);
+                } else if rivals.iter().all(|p| p.y != from.y) {
+                    san.push_str(&square[1..]);
+                } else {
+                    san.push_str(&square);
+                }
+            }
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&to.to_square());
+        san
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{play, square};
+
+    fn san(fen: &str, from: &str, to: &str) -> String {
+        let board = Board::from_fen(fen).unwrap();
+        board.san(square(from), square(to))
+    }
+
+    #[test]
+    fn san_disambiguation() {
+        let fen = "4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1";
+        assert_eq!(san(fen, "a1", "d1"), "Rd1");
+        assert_eq!(san(fen, "e1", "g1"), "O-O");
+        assert_eq!(san(fen, "e1", "c1"), "O-O-O");
+
+        let fen = "4k3/8/8/8/8/8/R6R/4K3 w - - 0 1";
+        assert_eq!(san(fen, "a2", "d2"), "Rad2");
+        let fen = "4k3/8/8/8/R7/8/8/R3K3 w - - 0 1";
+        assert_eq!(san(fen, "a1", "a3"), "R1a3");
+        let fen = "7k/2N5/8/8/8/2N1N3/8/4K3 w - - 0 1";
+        assert_eq!(san(fen, "c3", "d5"), "Nc3d5");
+        let fen = "4k3/8/3p4/4P3/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san(fen, "e5", "d6"), "exd6");
+    }
+
+    #[test]
+    fn export_fools_mate() {
+        let mut board = Board::init();
+        let mut history = History::new(&board);
+        play(&mut board, &mut history, "f2", "f3");
+        play(&mut board, &mut history, "e7", "e5");
+        play(&mut board, &mut history, "g2", "g4");
+        play(&mut board, &mut history, "d8", "h4");
+        let pgn = history.to_pgn(&board);
+        assert!(pgn.starts_with("[Event \"Tauri Chess game\"]\n[Site \"?\"]\n[Date \""));
+        assert!(pgn.contains("[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+        assert!(!pgn.contains("[FEN"));
+    }
+
+    #[test]
+    fn export_resignation_and_agreed_draw() {
+        let mut board = Board::init();
+        let mut history = History::new(&board);
+        play(&mut board, &mut history, "e2", "e4");
+        board.status = GameStatus::Resignation { white: false };
+        let pgn = history.to_pgn(&board);
+        assert!(pgn.contains("[Result \"1-0\"]"));
+        assert!(pgn.ends_with("\n1. e4 {Black resigns} 1-0\n"));
+
+        board.status = GameStatus::Draw(DrawReason::Agreement);
+        assert!(history
+            .to_pgn(&board)
+            .ends_with("\n1. e4 {Draw agreed} 1/2-1/2\n"));
+        let (board, history) = from_pgn(&history.to_pgn(&board)).unwrap();
+        assert_eq!(history.moves.len(), 1);
+        assert_eq!(board.status, GameStatus::Draw(DrawReason::Agreement));
+
+        let (board, _) = from_pgn("[Result \"0-1\"]\n\n1. e4").unwrap();
+        assert_eq!(board.status, GameStatus::Resignation { white: true });
+        let (board, _) = from_pgn("1. e4 e5 *").unwrap();
+        assert_eq!(board.status, GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn export_from_position() {
+        let fen = "4k3/8/8/8/8/8/8/4K2R b K - 0 30";
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut history = History::new(&board);
+        play(&mut board, &mut history, "e8", "d7");
+        play(&mut board, &mut history, "h1", "h7");
+        let pgn = history.to_pgn(&board);
+        assert!(pgn.contains(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", fen)));
+        assert!(pgn.ends_with("\n30... Kd7 31. Rh7+ *\n"));
+    }
+
+    #[test]
+    fn import_exported_game() {
+        let pgn = "[Event \"Test\"]\n[Result \"0-1\"]\n\n\
+                   1. f3 e5 2. g4 {blunder} (2. e4) Qh4# 0-1\n";
+        let (board, history) = from_pgn(pgn).unwrap();
+        assert_eq!(board.status, GameStatus::Checkmate);
+        let sans: Vec<&str> = history.moves.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(sans, vec!["f3", "e5", "g4", "Qh4#"]);
+
+        let (replayed, _) = from_pgn(&history.to_pgn(&board)).unwrap();
+        assert_eq!(replayed.to_fen(), board.to_fen());
+    }
+
+    #[test]
+    fn import_special_moves() {
+        let pgn = "[SetUp \"1\"]\n[FEN \"4k3/1P6/8/8/8/8/8/R3K3 w Q - 0 1\"]\n\n\
+                   1. O-O-O Kf7 2.b8=Q Kg6 *";
+        let (board, history) = from_pgn(pgn).unwrap();
+        assert_eq!(board.to_fen(), "1Q6/8/6k1/8/8/8/8/2KR4 w - - 1 3");
+        assert_eq!(history.moves[2].promotion, Some(FigureType::Queen));
+        assert_eq!(history.moves[2].san, "b8=Q");
+    }
+
+    #[test]
+    fn import_castling_with_zeros() {
+        let pgn = "1. d4 d5 2. Nc3 Nc6 3. Bf4 Bf5 4. Qd2 Qd7 5. 0-0-0 e6 6. e3 Nf6 \
+                   7. Nf3 Be7 8. Be2 0-0";
+        let (board, history) = from_pgn(pgn).unwrap();
+        assert_eq!(history.moves[8].san, "O-O-O");
+        assert_eq!(history.moves[15].san, "O-O");
+        assert_eq!(
+            board.to_fen(),
+            "r4rk1/pppqbppp/2n1pn2/3p1b2/3P1B2/2N1PN2/PPPQBPPP/2KR3R w - - 5 9"
+        );
+        assert_eq!(from_pgn("1.e4 e5 2.Nf3").unwrap().1.moves.len(), 3);
+    }
+
+    #[test]
+    fn import_errors() {
+        assert_eq!(
+            from_pgn("1. e4 e5 2. Ke3").unwrap_err(),
+            "move 2. Ke3: illegal move"
+        );
+        let pgn = "[FEN \"4k3/8/8/8/8/8/R6R/4K3 w - - 0 1\"]\n1. Rd2";
+        assert_eq!(from_pgn(pgn).unwrap_err(), "move 1. Rd2: ambiguous move");
+        let pgn = "[FEN \"4k3/8/8/8/8/8/R6R/4K3 b - - 0 7\"]\n7... Kd8 8. Rad2 Qd7";
+        assert_eq!(from_pgn(pgn).unwrap_err(), "move 8... Qd7: illegal move");
+        assert_eq!(
+            from_pgn("1. e4 {unfinished").unwrap_err(),
+            "unterminated comment"
+        );
+    }
+}
@@ -0,0 +1,13 @@
+//! Shorthands shared by the tests.
+use crate::history::History;
+use crate::{Board, Position};
+
+/// Position of a square in algebraic notation like `"e4"`.
+pub(crate) fn square(square: &str) -> Position {
+    Position::from_square(square).unwrap()
+}
+
+/// Plays a move that needs no promotion.
+pub(crate) fn play(board: &mut Board, history: &mut History, from: &str, to: &str) {
+    history.play(board, square(from), square(to), None);
+}
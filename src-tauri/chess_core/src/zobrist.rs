@@ -0,0 +1,164 @@
+//! Zobrist keys, see https://www.chessprogramming.org/Zobrist_Hashing. The hash of a position
+//! is the xor of the keys of its features, so a move only needs to xor the keys of the
+//! features it changes.
+use crate::{bitboard, is_figures_turn, Board, Figure, FigureType};
+
+/// Keys by color with white at index 1, kind and square
+const PIECES: [[[u64; 64]; 6]; 2] = piece_keys();
+/// Key of black to move
+pub(crate) const BLACK_TO_MOVE: u64 = key(768);
+/// Keys by color with white at index 1 and the rook's file, queenside first
+const CASTLING: [[u64; 2]; 2] = [[key(769), key(770)], [key(771), key(772)]];
+const EN_PASSANT_FILES: [u64; 8] = en_passant_keys();
+
+/// The `n`th output of the splitmix64 generator, so the keys are the same in every build.
+const fn key(n: u64) -> u64 {
+    let mut z = (n + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const fn piece_keys() -> [[[u64; 64]; 6]; 2] {
+    let mut keys = [[[0; 64]; 6]; 2];
+    let mut i = 0;
+    while i < 768 {
+        keys[i / 384][i / 64 % 6][i % 64] = key(i as u64);
+        i += 1;
+    }
+    keys
+}
+
+const fn en_passant_keys() -> [u64; 8] {
+    let mut keys = [0; 8];
+    let mut file = 0;
+    while file < 8 {
+        keys[file] = key(773 + file as u64);
+        file += 1;
+    }
+    keys
+}
+
+impl Figure {
+    /// Key of the figure on its position.
+    pub(crate) fn key(&self) -> u64 {
+        bitboard::square(self.position).map_or(0, |square| {
+            PIECES[self.white as usize][self.kind.index()][square]
+        })
+    }
+}
+
+impl Board {
+    /// Hash of the position computed from scratch.
+    pub(crate) fn compute_hash(&self) -> u64 {
+        let figures = self
+            .figures
+            .iter()
+            .filter(|figure| figure.alive)
+            .fold(0, |hash, figure| hash ^ figure.key());
+        let white = is_figures_turn(true, self.round);
+        let side = if white { 0 } else { BLACK_TO_MOVE };
+        figures ^ side ^ self.castling_key() ^ self.en_passant_key(white)
+    }
+
+    pub(crate) fn castling_key(&self) -> u64 {
+        let mut key = 0;
+        for white in [true, false] {
+            for (i, rook_x) in [0, 7].into_iter().enumerate() {
+                if self.has_castling_right(white, rook_x) {
+                    key ^= CASTLING[white as usize][i];
+                }
+            }
+        }
+        key
+    }
+
+    /// Key of the en passant file, which only counts if a pawn of the given color could
+    /// actually capture there.
+    pub(crate) fn en_passant_key(&self, capturing_white: bool) -> u64 {
+        let capturable = self.en_passant.filter(|&position| {
+            self.attackers(position, capturing_white) & self.bitboards.kind(&FigureType::Pawn) != 0
+        });
+        capturable.map_or(0, |position| EN_PASSANT_FILES[position.x as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::History;
+    use crate::pgn::from_pgn;
+    use crate::test_util::play;
+
+    #[test]
+    fn keys_are_distinct() {
+        let mut keys: Vec<u64> = PIECES.iter().flatten().flatten().copied().collect();
+        keys.push(BLACK_TO_MOVE);
+        keys.extend(CASTLING.iter().flatten());
+        keys.extend(EN_PASSANT_FILES);
+        let count = keys.len();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), count);
+    }
+
+    #[test]
+    fn incremental_hash_matches_full_hash() {
+        let pgn = "1. e4 d5 2. exd5 Nf6 3. Bb5+ c6 4. dxc6 Qa5 5. cxb7+ Nbd7 6. bxa8=Q e6 \
+                   7. Nf3 Bc5 8. O-O O-O 9. d4 e5 10. dxe5 a6 11. e6 Kh8 12. exf7 g6";
+        let (mut board, mut history) = from_pgn(pgn).unwrap();
+        assert_eq!(board.hash, board.compute_hash());
+        while history.undo(&mut board) {
+            assert_eq!(board.hash, board.compute_hash());
+        }
+        assert_eq!(board.hash, Board::init().hash);
+        while history.redo(&mut board) {
+            assert_eq!(board.hash, board.compute_hash());
+        }
+
+        // Every legal move from a position full of special moves
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        for m in board.legal_moves() {
+            let after = board.after_move(&m);
+            assert_eq!(after.hash, after.compute_hash(), "{}", m.to_uci());
+            for reply in after.legal_moves() {
+                let after = after.after_move(&reply);
+                assert_eq!(after.hash, after.compute_hash());
+            }
+        }
+    }
+
+    #[test]
+    fn hash_covers_castling_and_en_passant() {
+        // The rook returns, but castling is not possible anymore
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let mut history = History::new(&board);
+        let start = board.hash;
+        for (from, to) in [("h1", "h2"), ("e8", "d8"), ("h2", "h1"), ("d8", "e8")] {
+            play(&mut board, &mut history, from, to);
+        }
+        assert_ne!(board.hash, start);
+        assert_eq!(
+            board.hash,
+            Board::from_fen("4k3/8/8/8/8/8/8/4K2R w - - 4 3")
+                .unwrap()
+                .hash
+        );
+
+        // Without a pawn to capture en passant the double step doesn't count
+        let hash = |fen| Board::from_fen(fen).unwrap().hash;
+        assert_eq!(
+            hash("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1"),
+            hash("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1")
+        );
+        assert_ne!(
+            hash("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1"),
+            hash("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1")
+        );
+        assert_ne!(
+            hash("4k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            hash("4k3/8/8/8/8/8/8/4K3 b - - 0 1")
+        );
+    }
+}
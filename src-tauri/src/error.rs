@@ -0,0 +1,144 @@
+use crate::registry::GameId;
+use crate::{FigureType, Position};
+use serde::Serialize;
+use std::fmt;
+
+/// Why a command was refused. Serialized with a `kind` tag, like
+/// `{"kind": "IllegalMove", "from": {"x": 4, "y": 1}, "to": {"x": 4, "y": 4}}`, so the
+/// frontend can explain it.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind")]
+pub(crate) enum ChessError {
+    NotYourTurn,
+    NoPieceAtSquare {
+        position: Position,
+    },
+    IllegalMove {
+        from: Position,
+        to: Position,
+    },
+    GameOver,
+    OutOfBounds {
+        x: i32,
+        y: i32,
+    },
+    PromotionPending,
+    NoPromotionPending,
+    InvalidPromotion {
+        figure: FigureType,
+    },
+    NoDrawToClaim,
+    NoDrawOffered,
+    InvalidFen {
+        message: String,
+    },
+    InvalidPgn {
+        message: String,
+    },
+    /// A move in SAN or UCI notation that could not be read or is not legal
+    InvalidNotation {
+        notation: String,
+        message: String,
+    },
+    /// Counting the move tree this deep would take too long
+    PerftTooDeep {
+        max_depth: u32,
+    },
+    /// An engine failed, or the external UCI engine was not started
+    Engine {
+        message: String,
+    },
+    /// The external UCI engine is still answering another request
+    EngineBusy,
+    /// No open game has this ID
+    UnknownGame {
+        game_id: GameId,
+    },
+    /// A save file could not be written, read or replayed
+    Storage {
+        message: String,
+    },
+}
+
+impl fmt::Display for ChessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChessError::NotYourTurn => write!(f, "it is not your turn"),
+            ChessError::NoPieceAtSquare { position } => {
+                write!(f, "there is no piece on {}", position.to_square())
+            }
+            ChessError::IllegalMove { from, to } => write!(
+                f,
+                "moving from {} to {} is illegal",
+                from.to_square(),
+                to.to_square()
+            ),
+            ChessError::GameOver => write!(f, "the game is over"),
+            ChessError::OutOfBounds { x, y } => write!(f, "({}, {}) is not on the board", x, y),
+            ChessError::PromotionPending => write!(f, "a pawn is waiting for promotion"),
+            ChessError::NoPromotionPending => write!(f, "no pawn is waiting for promotion"),
+            ChessError::InvalidPromotion { figure } => {
+                write!(f, "a pawn can't become a {:?}", figure)
+            }
+            ChessError::NoDrawToClaim => write!(f, "there is no draw to claim"),
+            ChessError::NoDrawOffered => write!(f, "no draw was offered"),
+            ChessError::InvalidFen { message } => write!(f, "invalid FEN: {}", message),
+            ChessError::InvalidPgn { message } => write!(f, "invalid PGN: {}", message),
+            ChessError::InvalidNotation { notation, message } => {
+                write!(f, "invalid move {}: {}", notation, message)
+            }
+            ChessError::PerftTooDeep { max_depth } => {
+                write!(f, "perft is limited to depth {}", max_depth)
+            }
+            ChessError::Engine { message } => write!(f, "engine error: {}", message),
+            ChessError::EngineBusy => write!(f, "the engine is busy"),
+            ChessError::UnknownGame { game_id } => write!(f, "there is no game {}", game_id),
+            ChessError::Storage { message } => {
+                write!(f, "could not save or load the game: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChessError {}
+
+/// The position of a clicked square, if it is on the board.
+pub(crate) fn on_board(x: i32, y: i32) -> Result<Position, ChessError> {
+    if (0..8).contains(&x) && (0..8).contains(&y) {
+        Ok(Position::new(x, y))
+    } else {
+        Err(ChessError::OutOfBounds { x, y })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_with_kind_tag() {
+        let error = ChessError::IllegalMove {
+            from: Position::new(4, 1),
+            to: Position::new(4, 4),
+        };
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"kind":"IllegalMove","from":{"x":4,"y":1},"to":{"x":4,"y":4}}"#
+        );
+        assert_eq!(error.to_string(), "moving from e2 to e5 is illegal");
+        assert_eq!(
+            serde_json::to_string(&ChessError::GameOver).unwrap(),
+            r#"{"kind":"GameOver"}"#
+        );
+    }
+
+    #[test]
+    fn positions_on_board() {
+        assert_eq!(on_board(0, 7), Ok(Position::new(0, 7)));
+        assert_eq!(on_board(8, 0), Err(ChessError::OutOfBounds { x: 8, y: 0 }));
+        assert_eq!(
+            on_board(-1, 3).unwrap_err().to_string(),
+            "(-1, 3) is not on the board"
+        );
+    }
+}
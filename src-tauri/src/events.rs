@@ -0,0 +1,250 @@
+//! Events pushed to every window when a game changes, so no window has to poll the board.
+use crate::registry::GameId;
+use chess_core::clock::ClockTimes;
+use chess_core::history::History;
+use chess_core::{is_figures_turn, Board, Figure, FigureType, GameStatus, Position};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+/// Something that happened in a game, sent as the event `name()` with the game ID added to the
+/// payload, like `{"game_id": 0, "white": true}` for `check`.
+#[derive(Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub(crate) enum GameEvent {
+    /// The board changed by a move or otherwise. Carries only the pieces still on the board.
+    PositionChanged {
+        figures: Vec<Figure>,
+        round: i32,
+        status: GameStatus,
+        pending_promotion: Option<Position>,
+    },
+    MoveMade {
+        from: Position,
+        to: Position,
+        promotion: Option<FigureType>,
+        san: String,
+        /// Color of the moving side
+        white: bool,
+    },
+    /// A move took a piece, sent right after its `MoveMade`
+    Capture {
+        figure: Figure,
+    },
+    /// The side to move is in check
+    Check {
+        white: bool,
+    },
+    GameOver {
+        status: GameStatus,
+    },
+    /// A player offered a draw, which the opponent may answer in the next round
+    DrawOffered {
+        white: bool,
+        round: i32,
+    },
+    /// Remaining time while a clock runs, about ten times a second
+    ClockTick {
+        times: ClockTimes,
+    },
+}
+
+impl GameEvent {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            GameEvent::PositionChanged { .. } => "position-changed",
+            GameEvent::MoveMade { .. } => "move-made",
+            GameEvent::Capture { .. } => "capture",
+            GameEvent::Check { .. } => "check",
+            GameEvent::GameOver { .. } => "game-over",
+            GameEvent::DrawOffered { .. } => "draw-offered",
+            GameEvent::ClockTick { .. } => "clock-tick",
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct Payload {
+    game_id: GameId,
+    #[serde(flatten)]
+    event: GameEvent,
+}
+
+/// Sends the events of one game to all windows, or nowhere without an app.
+#[derive(Clone, Default)]
+pub(crate) struct Emitter {
+    app: Option<AppHandle>,
+    game_id: GameId,
+}
+
+impl Emitter {
+    pub(crate) fn new(app: Option<AppHandle>, game_id: GameId) -> Emitter {
+        Emitter { app, game_id }
+    }
+
+    pub(crate) fn emit(&self, event: GameEvent) {
+        if let Some(app) = &self.app {
+            let name = event.name();
+            let payload = Payload {
+                game_id: self.game_id,
+                event,
+            };
+            if let Err(e) = app.emit_all(name, payload) {
+                eprintln!("could not send {}: {}", name, e);
+            }
+        }
+    }
+}
+
+/// What the windows were last told about a game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Published {
+    /// Number of complete moves, not counting a pawn waiting for promotion
+    moves: usize,
+    status: GameStatus,
+}
+
+impl Published {
+    pub(crate) fn of(board: &Board, history: &History) -> Published {
+        Published {
+            moves: history.moves.len() - usize::from(board.pending_promotion.is_some()),
+            status: board.status,
+        }
+    }
+
+    /// The events telling the windows what changed since they were last told, in order.
+    /// Only moves beyond the ones already published count as made, so taking moves back just
+    /// changes the position.
+    pub(crate) fn changes(&mut self, board: &Board, history: &History) -> Vec<GameEvent> {
+        let now = Published::of(board, history);
+        let mut events = Vec::new();
+        for (i, played) in history.moves[..now.moves]
+            .iter()
+            .enumerate()
+            .skip(self.moves)
+        {
+            let round = board.round - (now.moves - i) as i32;
+            events.push(GameEvent::MoveMade {
+                from: played.from,
+                to: played.to,
+                promotion: played.promotion.clone(),
+                san: played.san.clone(),
+                white: is_figures_turn(true, round),
+            });
+            if let Some(figure) = played.captured(board) {
+                events.push(GameEvent::Capture {
+                    figure: figure.clone(),
+                });
+            }
+        }
+        events.push(GameEvent::PositionChanged {
+            figures: board.alive_figures().cloned().collect(),
+            round: board.round,
+            status: board.status,
+            pending_promotion: board.pending_promotion,
+        });
+        if board.status == GameStatus::Check && now != *self {
+            events.push(GameEvent::Check {
+                white: is_figures_turn(true, board.round),
+            });
+        }
+        if board.status.is_decided() && !self.status.is_decided() {
+            events.push(GameEvent::GameOver {
+                status: board.status,
+            });
+        }
+        *self = now;
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::square;
+    use chess_core::pgn::from_pgn;
+
+    fn names(events: &[GameEvent]) -> Vec<&'static str> {
+        events.iter().map(GameEvent::name).collect()
+    }
+
+    #[test]
+    fn publishes_new_moves() {
+        let (mut board, mut history) = from_pgn("1. e4 f5").unwrap();
+        let mut published = Published::of(&board, &history);
+        history.play(&mut board, square("e4"), square("f5"), None);
+        history.play(&mut board, square("g7"), square("g6"), None);
+        let events = published.changes(&board, &history);
+        assert_eq!(
+            names(&events),
+            ["move-made", "capture", "move-made", "position-changed"]
+        );
+        assert!(
+            matches!(&events[0], GameEvent::MoveMade { white: true, san, .. } if san == "exf5")
+        );
+        assert!(matches!(&events[1], GameEvent::Capture { figure } if !figure.white));
+        assert!(matches!(
+            events[2],
+            GameEvent::MoveMade { white: false, .. }
+        ));
+        assert!(
+            matches!(&events[3], GameEvent::PositionChanged { figures, .. } if figures.len() == 31)
+        );
+
+        history.play(&mut board, square("d1"), square("h5"), None);
+        history.play(&mut board, square("g6"), square("h5"), None);
+        assert_eq!(
+            names(&published.changes(&board, &history)),
+            ["move-made", "move-made", "capture", "position-changed"]
+        );
+        history.undo(&mut board);
+        history.undo(&mut board);
+        assert_eq!(
+            names(&published.changes(&board, &history)),
+            ["position-changed"]
+        );
+    }
+
+    #[test]
+    fn publishes_check_and_game_over() {
+        let (mut board, mut history) = from_pgn("1. f3 e5 2. g4").unwrap();
+        let mut published = Published::of(&board, &history);
+        history.play(&mut board, square("d8"), square("h4"), None);
+        let events = published.changes(&board, &history);
+        assert_eq!(
+            names(&events),
+            ["move-made", "position-changed", "game-over"]
+        );
+        assert!(matches!(
+            events[2],
+            GameEvent::GameOver {
+                status: GameStatus::Checkmate
+            }
+        ));
+
+        let mut board = Board::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut history = History::new(&board);
+        let mut published = Published::of(&board, &history);
+        history.play(&mut board, square("b7"), square("b8"), None);
+        assert_eq!(
+            names(&published.changes(&board, &history)),
+            ["position-changed"]
+        );
+        history.promote(&mut board, FigureType::Queen);
+        let events = published.changes(&board, &history);
+        assert_eq!(names(&events), ["move-made", "position-changed", "check"]);
+        assert!(matches!(&events[0], GameEvent::MoveMade { san, .. } if san == "b8=Q+"));
+        assert!(matches!(events[2], GameEvent::Check { white: false }));
+    }
+
+    #[test]
+    fn payload_contains_game_id() {
+        let payload = Payload {
+            game_id: 3,
+            event: GameEvent::Check { white: true },
+        };
+        assert_eq!(
+            serde_json::to_string(&payload).unwrap(),
+            r#"{"game_id":3,"white":true}"#
+        );
+    }
+}
@@ -0,0 +1,1174 @@
+use chess_core::clock::{Clock, ClockTimes, TimeControl};
+use chess_core::draw::DrawReason;
+use chess_core::history::History;
+use chess_core::{
+    engine, is_figures_turn, perft, pgn, Board, FigureType, GameStatus, Move, MoveOptions, Position,
+};
+use error::ChessError;
+use events::{Emitter, GameEvent, Published};
+use registry::{GameId, GameSummary, Games};
+use save::SavedGame;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{Manager, State};
+
+mod error;
+mod events;
+mod registry;
+mod save;
+#[cfg(test)]
+mod test_util;
+mod uci;
+
+const ENGINE_DEPTH: u32 = 4;
+const ENGINE_TIME: Duration = Duration::from_secs(2);
+const MAX_PERFT_DEPTH: u32 = 5;
+const CLOCK_TICK: Duration = Duration::from_millis(100);
+/// How often a running clock is autosaved, bounding the time lost when the app closes
+const CLOCK_SAVE: Duration = Duration::from_secs(5);
+
+#[tauri::command]
+fn position_interaction(
+    games: State<Games>,
+    game_id: GameId,
+    x: i32,
+    y: i32,
+) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let position = error::on_board(x, y)?;
+    let mut board = lock(&game.board);
+    let mut clock = lock(&game.clock);
+    ensure_ongoing(&mut board, &mut clock)?;
+    if board.pending_promotion.is_some() {
+        return Err(ChessError::PromotionPending);
+    }
+    let mut selected = lock(&game.selected);
+    match selected.take() {
+        // Clicking the selected figure again deselects it
+        Some(from) if from == position => Ok(()),
+        Some(from) => {
+            let figure_option = board
+                .get_figure_from_position(from)
+                .ok_or(ChessError::NoPieceAtSquare { position: from })?
+                .get_legal_move_options(&board);
+            if !figure_option.movable.contains(&position)
+                && !figure_option.killable.contains(&position)
+            {
+                return Err(ChessError::IllegalMove { from, to: position });
+            }
+            let mut history = lock(&game.history);
+            history.play(&mut board, from, position, None);
+            press_clock(&board, &mut clock);
+            let player = lock(&game.player);
+            game.changed_locked(&board, &history, &player, &clock);
+            reply_in_background(&game);
+            Ok(())
+        }
+        None => {
+            let figure = board
+                .get_figure_from_position(position)
+                .ok_or(ChessError::NoPieceAtSquare { position })?;
+            if !is_figures_turn(figure.white, board.round)
+                || lock(&game.player).is_engines_turn(board.round)
+            {
+                return Err(ChessError::NotYourTurn);
+            }
+            *selected = Some(position);
+            Ok(())
+        }
+    }
+}
+
+#[tauri::command]
+fn promote(games: State<Games>, game_id: GameId, kind: FigureType) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let mut board = lock(&game.board);
+    let mut history = lock(&game.history);
+    let mut clock = lock(&game.clock);
+    ensure_ongoing(&mut board, &mut clock)?;
+    if board.pending_promotion.is_none() {
+        return Err(ChessError::NoPromotionPending);
+    }
+    if !history.promote(&mut board, kind.clone()) {
+        return Err(ChessError::InvalidPromotion { figure: kind });
+    }
+    press_clock(&board, &mut clock);
+    let player = lock(&game.player);
+    game.changed_locked(&board, &history, &player, &clock);
+    reply_in_background(&game);
+    Ok(())
+}
+
+/// Plays a move without selecting the figure first. Without a promotion a pawn reaching the
+/// last rank waits for `promote`.
+#[tauri::command]
+fn make_move(
+    games: State<Games>,
+    game_id: GameId,
+    from: Position,
+    to: Position,
+    promotion: Option<FigureType>,
+) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    play_move(&game, from, to, promotion)?;
+    reply_in_background(&game);
+    Ok(())
+}
+
+#[tauri::command]
+fn make_move_san(games: State<Games>, game_id: GameId, san: String) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let (from, to, promotion) =
+        lock(&game.board)
+            .parse_san(&san)
+            .map_err(|message| ChessError::InvalidNotation {
+                notation: san.clone(),
+                message,
+            })?;
+    play_move(&game, from, to, promotion)?;
+    reply_in_background(&game);
+    Ok(())
+}
+
+#[tauri::command]
+fn make_move_uci(games: State<Games>, game_id: GameId, uci: String) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let m = Move::from_uci(&uci).ok_or_else(|| ChessError::InvalidNotation {
+        notation: uci.clone(),
+        message: "expected a move like e2e4 or e7e8q".to_string(),
+    })?;
+    play_move(&game, m.from, m.to, m.promotion)?;
+    reply_in_background(&game);
+    Ok(())
+}
+
+#[tauri::command]
+fn undo_move(games: State<Games>, game_id: GameId) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    step_history(&game, History::undo)
+}
+
+#[tauri::command]
+fn redo_move(games: State<Games>, game_id: GameId) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    step_history(&game, History::redo)
+}
+
+#[tauri::command]
+fn set_player_color(games: State<Games>, game_id: GameId, white: bool) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let board = lock(&game.board);
+    let history = lock(&game.history);
+    let clock = lock(&game.clock);
+    let mut player = lock(&game.player);
+    player.white = Some(white);
+    game.changed_locked(&board, &history, &player, &clock);
+    reply_in_background(&game);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_time_control(
+    games: State<Games>,
+    game_id: GameId,
+    time_control: Option<TimeControl>,
+) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let board = lock(&game.board);
+    let mut clock = lock(&game.clock);
+    *clock = time_control.map(Clock::new);
+    run_clock(&board, &mut clock);
+    let history = lock(&game.history);
+    game.changed_locked(&board, &history, &lock(&game.player), &clock);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_remaining_time(
+    games: State<Games>,
+    game_id: GameId,
+) -> Result<Option<ClockTimes>, ChessError> {
+    let game = games.get(game_id)?;
+    let mut board = lock(&game.board);
+    let mut clock = lock(&game.clock);
+    check_flag(&mut board, &mut clock);
+    Ok(clock.as_ref().map(|clock| clock.times(Instant::now())))
+}
+
+#[tauri::command]
+fn claim_draw(games: State<Games>, game_id: GameId) -> Result<DrawReason, ChessError> {
+    let game = games.get(game_id)?;
+    let mut board = lock(&game.board);
+    let mut clock = lock(&game.clock);
+    ensure_ongoing(&mut board, &mut clock)?;
+    let reason = board.claimable_draw().ok_or(ChessError::NoDrawToClaim)?;
+    board.status = GameStatus::Draw(reason);
+    run_clock(&board, &mut clock);
+    let history = lock(&game.history);
+    game.changed_locked(&board, &history, &lock(&game.player), &clock);
+    Ok(reason)
+}
+
+/// Offers the opponent a draw and returns whether the game ended in one. The engine answers
+/// once it has searched the position, a human opponent with `accept_draw` or `decline_draw`
+/// or by moving on.
+#[tauri::command]
+async fn offer_draw(games: State<'_, Games>, game_id: GameId) -> Result<bool, ChessError> {
+    let game = games.get(game_id)?;
+    {
+        let mut board = lock(&game.board);
+        let mut clock = lock(&game.clock);
+        ensure_ongoing(&mut board, &mut clock)?;
+        if lock(&game.player).white.is_none() {
+            let offer = DrawOffer {
+                white: is_figures_turn(true, board.round),
+                round: board.round,
+            };
+            *lock(&game.draw_offer) = Some(offer);
+            game.events.emit(GameEvent::DrawOffered {
+                white: offer.white,
+                round: offer.round,
+            });
+            return Ok(false);
+        }
+    }
+    tauri::async_runtime::spawn_blocking(move || engine_answers_draw(&game))
+        .await
+        .map_err(|e| ChessError::Engine {
+            message: e.to_string(),
+        })?
+}
+
+#[tauri::command]
+fn accept_draw(games: State<Games>, game_id: GameId) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let mut board = lock(&game.board);
+    let mut clock = lock(&game.clock);
+    ensure_ongoing(&mut board, &mut clock)?;
+    take_draw_offer(&board, &game)?;
+    board.status = GameStatus::Draw(DrawReason::Agreement);
+    run_clock(&board, &mut clock);
+    let history = lock(&game.history);
+    game.changed_locked(&board, &history, &lock(&game.player), &clock);
+    Ok(())
+}
+
+#[tauri::command]
+fn decline_draw(games: State<Games>, game_id: GameId) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let board = lock(&game.board);
+    take_draw_offer(&board, &game).map(|_| ())
+}
+
+/// Gives up the game for the human player, or for the side to move when both are human.
+#[tauri::command]
+fn resign(games: State<Games>, game_id: GameId) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let mut board = lock(&game.board);
+    let mut clock = lock(&game.clock);
+    ensure_ongoing(&mut board, &mut clock)?;
+    let history = lock(&game.history);
+    let player = lock(&game.player);
+    let white = player
+        .white
+        .unwrap_or_else(|| is_figures_turn(true, board.round));
+    board.status = GameStatus::Resignation { white };
+    *lock(&game.draw_offer) = None;
+    *lock(&game.selected) = None;
+    run_clock(&board, &mut clock);
+    game.changed_locked(&board, &history, &player, &clock);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_board(games: State<Games>, game_id: GameId) -> Result<Board, ChessError> {
+    let game = games.get(game_id)?;
+    let board = lock(&game.board).clone();
+    Ok(board)
+}
+
+#[tauri::command]
+fn get_status(games: State<Games>, game_id: GameId) -> Result<GameStatus, ChessError> {
+    let status = lock(&games.get(game_id)?.board).status;
+    Ok(status)
+}
+
+#[tauri::command]
+fn load_fen(games: State<Games>, game_id: GameId, fen: String) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let board = Board::from_fen(&fen).map_err(|message| ChessError::InvalidFen { message })?;
+    *lock(&game.history) = History::new(&board);
+    reset_clock(&board, &mut lock(&game.clock));
+    *lock(&game.draw_offer) = None;
+    *lock(&game.selected) = None;
+    *lock(&game.board) = board;
+    game.reset_published();
+    game.changed();
+    Ok(())
+}
+
+#[tauri::command]
+fn get_fen(games: State<Games>, game_id: GameId) -> Result<String, ChessError> {
+    let fen = lock(&games.get(game_id)?.board).to_fen();
+    Ok(fen)
+}
+
+#[tauri::command]
+fn get_pgn(games: State<Games>, game_id: GameId) -> Result<String, ChessError> {
+    let game = games.get(game_id)?;
+    let board = lock(&game.board);
+    let pgn = lock(&game.history).to_pgn(&board);
+    Ok(pgn)
+}
+
+#[tauri::command]
+fn load_pgn(games: State<Games>, game_id: GameId, pgn: String) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let (board, history) =
+        pgn::from_pgn(&pgn).map_err(|message| ChessError::InvalidPgn { message })?;
+    *lock(&game.history) = history;
+    reset_clock(&board, &mut lock(&game.clock));
+    *lock(&game.draw_offer) = None;
+    *lock(&game.selected) = None;
+    *lock(&game.board) = board;
+    game.reset_published();
+    game.changed();
+    Ok(())
+}
+
+#[tauri::command]
+fn create_game(games: State<Games>) -> GameId {
+    games.create()
+}
+
+#[tauri::command]
+fn list_games(games: State<Games>) -> Vec<GameSummary> {
+    games.list()
+}
+
+#[tauri::command]
+fn current_game(games: State<Games>) -> Option<GameId> {
+    games.current()
+}
+
+#[tauri::command]
+fn switch_game(games: State<Games>, game_id: GameId) -> Result<(), ChessError> {
+    games.switch(game_id)
+}
+
+#[tauri::command]
+fn close_game(games: State<Games>, game_id: GameId) -> Result<(), ChessError> {
+    games.close(game_id)
+}
+
+/// Continues a restored game by starting the clock and letting the engine move if it is its turn.
+#[tauri::command]
+fn resume_game(games: State<Games>, game_id: GameId) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let board = lock(&game.board);
+    let history = lock(&game.history);
+    let mut clock = lock(&game.clock);
+    run_clock(&board, &mut clock);
+    let player = lock(&game.player);
+    game.changed_locked(&board, &history, &player, &clock);
+    reply_in_background(&game);
+    Ok(())
+}
+
+#[tauri::command]
+fn save_game(games: State<Games>, game_id: GameId, path: PathBuf) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let board = lock(&game.board);
+    let history = lock(&game.history);
+    let player = lock(&game.player);
+    let clock = lock(&game.clock);
+    SavedGame::new(&board, &history, &player, &clock)
+        .write(&path)
+        .map_err(|message| ChessError::Storage { message })
+}
+
+#[tauri::command]
+fn load_game(games: State<Games>, game_id: GameId, path: PathBuf) -> Result<(), ChessError> {
+    let game = games.get(game_id)?;
+    let restored = SavedGame::read(&path)
+        .and_then(|saved| saved.restore())
+        .map_err(|message| ChessError::Storage { message })?;
+    game.replace(restored);
+    resume_game(games, game_id)
+}
+
+#[tauri::command]
+fn perft(games: State<Games>, game_id: GameId, depth: u32) -> Result<perft::Divide, ChessError> {
+    // Deeper trees keep the window busy for minutes
+    if depth > MAX_PERFT_DEPTH {
+        return Err(ChessError::PerftTooDeep {
+            max_depth: MAX_PERFT_DEPTH,
+        });
+    }
+    let board = lock(&games.get(game_id)?.board).clone();
+    Ok(board.divide(depth))
+}
+
+#[tauri::command]
+fn start_engine(engine: State<ExternalEngine>, path: String) -> Result<Option<String>, ChessError> {
+    let mut process = engine.try_process()?;
+    let started =
+        uci::UciEngine::spawn(&path, &[]).map_err(|message| ChessError::Engine { message })?;
+    let name = started.name.clone();
+    *process = Some(started);
+    Ok(name)
+}
+
+#[tauri::command]
+fn stop_engine(engine: State<ExternalEngine>) -> Result<(), ChessError> {
+    *engine.try_process()? = None;
+    Ok(())
+}
+
+/// Searches on a blocking task, so the window stays responsive. A second request while the
+/// engine is still searching is refused with `EngineBusy`.
+#[tauri::command]
+async fn analyze_position(
+    games: State<'_, Games>,
+    game_id: GameId,
+    engine: State<'_, ExternalEngine>,
+    milliseconds: u64,
+) -> Result<uci::Analysis, ChessError> {
+    let game = games.get(game_id)?;
+    if lock(&game.board).pending_promotion.is_some() {
+        return Err(ChessError::PromotionPending);
+    }
+    let history = lock(&game.history).clone();
+    let engine = ExternalEngine {
+        process: Arc::clone(&engine.process),
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut process = engine.try_process()?;
+        let process = process.as_mut().ok_or_else(|| ChessError::Engine {
+            message: "no engine started".to_string(),
+        })?;
+        process
+            .analyze(&history, Duration::from_millis(milliseconds))
+            .map_err(|message| ChessError::Engine { message })
+    })
+    .await
+    .map_err(|e| ChessError::Engine {
+        message: e.to_string(),
+    })?
+}
+
+#[tauri::command]
+fn get_options(
+    games: State<Games>,
+    game_id: GameId,
+    x: i32,
+    y: i32,
+) -> Result<MoveOptions, ChessError> {
+    let game = games.get(game_id)?;
+    let position = error::on_board(x, y)?;
+    let board = lock(&game.board);
+    let figure = board
+        .get_figure_from_position(position)
+        .ok_or(ChessError::NoPieceAtSquare { position })?;
+    if !is_figures_turn(figure.white, board.round) {
+        return Err(ChessError::NotYourTurn);
+    }
+    Ok(figure.get_legal_move_options(&board))
+}
+
+/// Locks `mutex`, going on with its data if a thread panicked while holding it, so one failed
+/// command doesn't make every later one panic too.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub fn run() {
+    tauri::Builder::default()
+        .setup(|app| {
+            let autosave_dir = app
+                .path_resolver()
+                .app_data_dir()
+                .map(|dir| dir.join(save::AUTOSAVE_DIR));
+            app.manage(Games::restore(autosave_dir, Some(app.handle())));
+            let app = app.handle();
+            thread::spawn(move || loop {
+                app.state::<Games>().tick();
+                thread::sleep(CLOCK_TICK);
+            });
+            Ok(())
+        })
+        .manage(ExternalEngine::default())
+        .invoke_handler(tauri::generate_handler![
+            get_board,
+            get_options,
+            get_status,
+            load_fen,
+            get_fen,
+            get_pgn,
+            load_pgn,
+            create_game,
+            list_games,
+            current_game,
+            switch_game,
+            close_game,
+            resume_game,
+            save_game,
+            load_game,
+            perft,
+            start_engine,
+            stop_engine,
+            analyze_position,
+            set_player_color,
+            set_time_control,
+            get_remaining_time,
+            claim_draw,
+            offer_draw,
+            accept_draw,
+            decline_draw,
+            resign,
+            position_interaction,
+            make_move,
+            make_move_san,
+            make_move_uci,
+            promote,
+            undo_move,
+            redo_move,
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+fn play_move(
+    game: &Game,
+    from: Position,
+    to: Position,
+    promotion: Option<FigureType>,
+) -> Result<(), ChessError> {
+    let from = error::on_board(from.x, from.y)?;
+    let to = error::on_board(to.x, to.y)?;
+    let mut board = lock(&game.board);
+    let mut clock = lock(&game.clock);
+    ensure_ongoing(&mut board, &mut clock)?;
+    if board.pending_promotion.is_some() {
+        return Err(ChessError::PromotionPending);
+    }
+    let figure = board
+        .get_figure_from_position(from)
+        .ok_or(ChessError::NoPieceAtSquare { position: from })?;
+    if !is_figures_turn(figure.white, board.round)
+        || lock(&game.player).is_engines_turn(board.round)
+    {
+        return Err(ChessError::NotYourTurn);
+    }
+    if let Some(kind @ (FigureType::Pawn | FigureType::King)) = &promotion {
+        return Err(ChessError::InvalidPromotion {
+            figure: kind.clone(),
+        });
+    }
+    let legal = board
+        .legal_moves()
+        .into_iter()
+        .any(|m| m.from == from && m.to == to && (promotion.is_none() || m.promotion == promotion));
+    if !legal {
+        return Err(ChessError::IllegalMove { from, to });
+    }
+    *lock(&game.selected) = None;
+    let mut history = lock(&game.history);
+    history.play(&mut board, from, to, promotion);
+    press_clock(&board, &mut clock);
+    let player = lock(&game.player);
+    game.changed_locked(&board, &history, &player, &clock);
+    Ok(())
+}
+
+/// Takes back or replays a move, together with the engine's reply. A decided game keeps its
+/// result, since a resignation or agreed draw could not be replayed.
+fn step_history(game: &Game, step: fn(&mut History, &mut Board) -> bool) -> Result<(), ChessError> {
+    let mut board = lock(&game.board);
+    let mut history = lock(&game.history);
+    let mut clock = lock(&game.clock);
+    ensure_ongoing(&mut board, &mut clock)?;
+    let player = lock(&game.player);
+    while step(&mut history, &mut board) && player.is_engines_turn(board.round) {}
+    run_clock(&board, &mut clock);
+    *lock(&game.selected) = None;
+    game.changed_locked(&board, &history, &player, &clock);
+    Ok(())
+}
+
+fn take_draw_offer(board: &Board, game: &Game) -> Result<DrawOffer, ChessError> {
+    let mut draw_offer = lock(&game.draw_offer);
+    match *draw_offer {
+        Some(offer) if offer.is_open(board.round) => {
+            *draw_offer = None;
+            Ok(offer)
+        }
+        _ => Err(ChessError::NoDrawOffered),
+    }
+}
+
+fn reply_in_background(game: &Arc<Game>) {
+    let game = Arc::clone(game);
+    thread::spawn(move || engine_reply(&game));
+}
+
+/// Lets the engine play the side the human player did not choose. The search runs on a copy
+/// of the board without holding any lock.
+fn engine_reply(game: &Game) {
+    let position = {
+        let board = lock(&game.board);
+        let player = lock(&game.player);
+        if !player.is_engines_turn(board.round)
+            || board.status.is_decided()
+            || board.pending_promotion.is_some()
+        {
+            return;
+        }
+        board.clone()
+    };
+    let result = match engine::search(&position, ENGINE_DEPTH, ENGINE_TIME) {
+        Some(result) => result,
+        None => return,
+    };
+    let mut board = lock(&game.board);
+    let mut history = lock(&game.history);
+    let mut clock = lock(&game.clock);
+    let player = lock(&game.player);
+    // The game may have moved on while the engine was thinking, like by an undo
+    if board.to_fen() != position.to_fen()
+        || !player.is_engines_turn(board.round)
+        || board.status.is_decided()
+    {
+        return;
+    }
+    // The engine loses on time like anyone else
+    check_flag(&mut board, &mut clock);
+    if !board.status.is_decided() {
+        let m = result.best_move;
+        history.play(&mut board, m.from, m.to, m.promotion);
+        press_clock(&board, &mut clock);
+    }
+    game.changed_locked(&board, &history, &player, &clock);
+}
+
+/// Lets the engine decide on a draw offer like `engine_reply` decides on a move, searching a
+/// copy of the board without holding any lock.
+fn engine_answers_draw(game: &Game) -> Result<bool, ChessError> {
+    let (position, engine_white) = {
+        let board = lock(&game.board);
+        match lock(&game.player).white {
+            Some(white) => (board.clone(), !white),
+            None => return Ok(false),
+        }
+    };
+    let accepts = engine::accepts_draw(&position, engine_white);
+    let mut board = lock(&game.board);
+    let mut clock = lock(&game.clock);
+    ensure_ongoing(&mut board, &mut clock)?;
+    // The game may have moved on while the engine was thinking
+    if !accepts || board.round != position.round || board.to_fen() != position.to_fen() {
+        return Ok(false);
+    }
+    board.status = GameStatus::Draw(DrawReason::Agreement);
+    run_clock(&board, &mut clock);
+    let history = lock(&game.history);
+    game.changed_locked(&board, &history, &lock(&game.player), &clock);
+    Ok(true)
+}
+
+fn ensure_ongoing(board: &mut Board, clock: &mut Option<Clock>) -> Result<(), ChessError> {
+    check_flag(board, clock);
+    if board.status.is_decided() {
+        Err(ChessError::GameOver)
+    } else {
+        Ok(())
+    }
+}
+
+fn check_flag(board: &mut Board, clock: &mut Option<Clock>) {
+    let now = Instant::now();
+    if let Some(clock) = clock {
+        if let Some(white) = clock.flag(now) {
+            clock.stop(now);
+            board.status = if board.has_mating_material(!white) {
+                GameStatus::Timeout
+            } else {
+                GameStatus::Draw(DrawReason::TimeoutVsInsufficientMaterial)
+            };
+        }
+    }
+}
+
+fn press_clock(board: &Board, clock: &mut Option<Clock>) {
+    let now = Instant::now();
+    match clock {
+        Some(clock) if board.status.is_decided() => clock.stop(now),
+        Some(clock) if board.pending_promotion.is_none() => clock.press(now),
+        _ => {}
+    }
+}
+
+fn run_clock(board: &Board, clock: &mut Option<Clock>) {
+    let now = Instant::now();
+    if let Some(clock) = clock {
+        if board.status.is_decided() {
+            clock.stop(now);
+        } else {
+            clock.start(is_figures_turn(true, board.round), now);
+        }
+    }
+}
+
+fn reset_clock(board: &Board, clock: &mut Option<Clock>) {
+    *clock = clock.as_ref().map(|clock| Clock::new(clock.time_control()));
+    run_clock(board, clock);
+}
+
+#[derive(Serialize, Default, Debug)]
+struct Player {
+    /// Color of the human player, the engine plays the other one once chosen
+    white: Option<bool>,
+}
+
+impl Player {
+    fn is_engines_turn(&self, round: i32) -> bool {
+        self.white
+            .is_some_and(|white| !is_figures_turn(white, round))
+    }
+}
+
+/// A draw offered by one player, which stands until the opponent answers it or moves on.
+#[derive(Serialize, Clone, Copy, Debug)]
+struct DrawOffer {
+    white: bool,
+    round: i32,
+}
+
+impl DrawOffer {
+    /// The opponent may answer on the turn right after the offering player moved, but not the
+    /// offering player itself.
+    fn is_open(&self, round: i32) -> bool {
+        round == self.round + 1 && is_figures_turn(!self.white, round)
+    }
+}
+
+#[derive(Serialize)]
+struct Game {
+    board: Mutex<Board>,
+    player: Mutex<Player>,
+    history: Mutex<History>,
+    clock: Mutex<Option<Clock>>,
+    draw_offer: Mutex<Option<DrawOffer>>,
+    /// Figure selected by the first click of `position_interaction`
+    selected: Mutex<Option<Position>>,
+    /// File the game is saved to after every change, `None` to not save it or once it is closed
+    #[serde(skip)]
+    autosave: Mutex<Option<PathBuf>>,
+    #[serde(skip)]
+    autosaved: Mutex<Option<Instant>>,
+    #[serde(skip)]
+    events: Emitter,
+    #[serde(skip)]
+    published: Mutex<Published>,
+}
+
+#[derive(Default)]
+struct ExternalEngine {
+    process: Arc<Mutex<Option<uci::UciEngine>>>,
+}
+
+impl ExternalEngine {
+    fn try_process(&self) -> Result<MutexGuard<'_, Option<uci::UciEngine>>, ChessError> {
+        match self.process.try_lock() {
+            Ok(process) => Ok(process),
+            Err(TryLockError::WouldBlock) => Err(ChessError::EngineBusy),
+            Err(TryLockError::Poisoned(poisoned)) => Ok(poisoned.into_inner()),
+        }
+    }
+}
+
+impl Game {
+    fn init() -> Self {
+        let board = Board::init();
+        let history = History::new(&board);
+        Game {
+            published: Mutex::new(Published::of(&board, &history)),
+            history: Mutex::new(history),
+            board: Mutex::new(board),
+            player: Mutex::new(Player::default()),
+            clock: Mutex::new(None),
+            draw_offer: Mutex::new(None),
+            selected: Mutex::new(None),
+            autosave: Mutex::new(None),
+            autosaved: Mutex::new(None),
+            events: Emitter::default(),
+        }
+    }
+
+    /// Continues the game saved in `autosave`, or starts a new one if there is no unfinished game
+    /// to continue. Fails if the file exists but can't be read.
+    fn restore(autosave: Option<PathBuf>) -> Result<Self, String> {
+        let saved = match autosave.as_deref().filter(|path| path.exists()) {
+            Some(path) => Some(SavedGame::read(path).and_then(|saved| saved.restore())?),
+            None => None,
+        };
+        let game = Game {
+            autosave: Mutex::new(autosave),
+            ..Game::init()
+        };
+        match saved {
+            Some(restored) if !restored.board.status.is_decided() => game.replace(restored),
+            _ => {}
+        }
+        Ok(game)
+    }
+
+    fn is_unfinished(&self) -> bool {
+        let board = lock(&self.board);
+        !board.status.is_decided() && !lock(&self.history).moves.is_empty()
+    }
+
+    fn replace(&self, restored: save::Restored) {
+        *lock(&self.history) = restored.history;
+        *lock(&self.player) = restored.player;
+        *lock(&self.clock) = restored.clock;
+        *lock(&self.draw_offer) = None;
+        *lock(&self.selected) = None;
+        *lock(&self.board) = restored.board;
+        self.reset_published();
+    }
+
+    /// Counts the moves on the board as known to the windows, for a position that was loaded
+    /// instead of played.
+    fn reset_published(&self) {
+        let board = lock(&self.board);
+        let history = lock(&self.history);
+        *lock(&self.published) = Published::of(&board, &history);
+    }
+
+    fn changed(&self) {
+        let board = lock(&self.board);
+        let history = lock(&self.history);
+        let player = lock(&self.player);
+        let clock = lock(&self.clock);
+        self.changed_locked(&board, &history, &player, &clock);
+    }
+
+    fn changed_locked(
+        &self,
+        board: &Board,
+        history: &History,
+        player: &Player,
+        clock: &Option<Clock>,
+    ) {
+        self.save_locked(board, history, player, clock);
+        let events = lock(&self.published).changes(board, history);
+        for event in events {
+            self.events.emit(event);
+        }
+    }
+
+    fn save_locked(
+        &self,
+        board: &Board,
+        history: &History,
+        player: &Player,
+        clock: &Option<Clock>,
+    ) {
+        if let Some(path) = &*lock(&self.autosave) {
+            if let Err(message) = SavedGame::new(board, history, player, clock).write(path) {
+                eprintln!("could not autosave the game: {}", message);
+            }
+            *lock(&self.autosaved) = Some(Instant::now());
+        }
+    }
+
+    fn tick(&self) {
+        let mut board = lock(&self.board);
+        let history = lock(&self.history);
+        let mut clock = lock(&self.clock);
+        let times = match clock.as_ref() {
+            Some(clock) => clock.times(Instant::now()),
+            None => return,
+        };
+        if times.running.is_none() {
+            return;
+        }
+        self.events.emit(GameEvent::ClockTick { times });
+        check_flag(&mut board, &mut clock);
+        let player = lock(&self.player);
+        if board.status.is_decided() {
+            self.changed_locked(&board, &history, &player, &clock);
+        } else if lock(&self.autosaved).is_none_or(|saved| saved.elapsed() >= CLOCK_SAVE) {
+            self.save_locked(&board, &history, &player, &clock);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::square;
+
+    #[test]
+    fn survives_poisoned_locks() {
+        let game = Game::init();
+        thread::scope(|scope| {
+            let poisoner = scope.spawn(|| {
+                let _board = game.board.lock().unwrap();
+                panic!("poison the board");
+            });
+            assert!(poisoner.join().is_err());
+        });
+        assert!(game.board.is_poisoned());
+        assert_eq!(play_move(&game, square("e2"), square("e4"), None), Ok(()));
+    }
+
+    #[test]
+    fn moves_without_clicks() {
+        let game = Game::init();
+        assert_eq!(play_move(&game, square("e2"), square("e4"), None), Ok(()));
+        assert_eq!(
+            play_move(&game, square("d2"), square("d4"), None),
+            Err(ChessError::NotYourTurn)
+        );
+        assert_eq!(
+            play_move(&game, square("e7"), square("e4"), None),
+            Err(ChessError::IllegalMove {
+                from: square("e7"),
+                to: square("e4"),
+            })
+        );
+        assert_eq!(
+            play_move(&game, square("e5"), square("e4"), None),
+            Err(ChessError::NoPieceAtSquare {
+                position: square("e5")
+            })
+        );
+        assert_eq!(
+            play_move(&game, Position::new(4, 6), Position::new(4, 8), None),
+            Err(ChessError::OutOfBounds { x: 4, y: 8 })
+        );
+        assert_eq!(play_move(&game, square("e7"), square("e5"), None), Ok(()));
+        assert_eq!(game.board.lock().unwrap().round, 2);
+
+        *game.board.lock().unwrap() = Board::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            play_move(&game, square("b7"), square("b8"), Some(FigureType::King)),
+            Err(ChessError::InvalidPromotion {
+                figure: FigureType::King
+            })
+        );
+        let knight = Some(FigureType::Knight);
+        assert_eq!(play_move(&game, square("b7"), square("b8"), knight), Ok(()));
+        assert_eq!(
+            game.board.lock().unwrap().to_fen(),
+            "1N2k3/8/8/8/8/8/8/4K3 b - - 0 1"
+        );
+    }
+
+    #[test]
+    fn restores_autosaved_game() {
+        let path = std::env::temp_dir()
+            .join(format!("tauri_chess_{}", std::process::id()))
+            .join("0.json");
+        let game = Game::restore(Some(path.clone())).unwrap();
+        assert_eq!(play_move(&game, square("e2"), square("e4"), None), Ok(()));
+        assert!(path.exists());
+
+        let restored = Game::restore(Some(path.clone())).unwrap();
+        assert_eq!(
+            restored.board.lock().unwrap().to_fen(),
+            game.board.lock().unwrap().to_fen()
+        );
+        assert_eq!(restored.history.lock().unwrap().moves.len(), 1);
+
+        *game.board.lock().unwrap() = Board::init();
+        game.board.lock().unwrap().status = GameStatus::Resignation { white: true };
+        game.changed();
+        assert_eq!(
+            Game::restore(Some(path.clone()))
+                .unwrap()
+                .board
+                .lock()
+                .unwrap()
+                .round,
+            0
+        );
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn autosaves_running_clock() {
+        let path = std::env::temp_dir()
+            .join(format!("tauri_chess_clock_{}", std::process::id()))
+            .join("0.json");
+        let game = Game::restore(Some(path.clone())).unwrap();
+        let ago = |secs| Instant::now() - Duration::from_secs(secs);
+        let mut clock = Clock::new(TimeControl {
+            base_ms: 60_000,
+            increment_ms: 0,
+            increment_kind: chess_core::clock::IncrementKind::Fischer,
+        });
+        clock.start(true, ago(3));
+        *game.clock.lock().unwrap() = Some(clock);
+        let restored_white_ms = || {
+            let restored = Game::restore(Some(path.clone())).unwrap();
+            let times = restored
+                .clock
+                .lock()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .times(Instant::now());
+            times.white_ms
+        };
+        game.tick();
+        assert!((56_000..=57_000).contains(&restored_white_ms()));
+
+        game.clock
+            .lock()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .start(true, ago(10));
+        game.tick();
+        assert!((56_000..=57_000).contains(&restored_white_ms()));
+        *game.autosaved.lock().unwrap() = Some(ago(5));
+        game.tick();
+        assert!(restored_white_ms() <= 50_000);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn engine_replies_to_moves() {
+        let game = Game::init();
+        game.player.lock().unwrap().white = Some(true);
+        play_move(&game, square("e2"), square("e4"), None).unwrap();
+        // Nobody may move the engine's pieces while it thinks
+        assert_eq!(
+            play_move(&game, square("e7"), square("e5"), None),
+            Err(ChessError::NotYourTurn)
+        );
+        engine_reply(&game);
+        assert_eq!(game.board.lock().unwrap().round, 2);
+        assert_eq!(game.history.lock().unwrap().moves.len(), 2);
+        engine_reply(&game);
+        assert_eq!(game.board.lock().unwrap().round, 2);
+    }
+
+    #[test]
+    fn undo_keeps_result() {
+        let game = Game::init();
+        play_move(&game, square("e2"), square("e4"), None).unwrap();
+        let resigned = GameStatus::Resignation { white: false };
+        game.board.lock().unwrap().status = resigned;
+        assert_eq!(
+            step_history(&game, History::undo),
+            Err(ChessError::GameOver)
+        );
+        assert_eq!(
+            step_history(&game, History::redo),
+            Err(ChessError::GameOver)
+        );
+        assert_eq!(game.board.lock().unwrap().status, resigned);
+        assert_eq!(game.history.lock().unwrap().moves.len(), 1);
+    }
+
+    #[test]
+    fn only_the_opponent_accepts_a_draw() {
+        let game = Game::init();
+        *game.draw_offer.lock().unwrap() = Some(DrawOffer {
+            white: true,
+            round: 0,
+        });
+        let board = game.board.lock().unwrap().clone();
+        assert_eq!(
+            take_draw_offer(&board, &game).err(),
+            Some(ChessError::NoDrawOffered)
+        );
+        play_move(&game, square("e2"), square("e4"), None).unwrap();
+        let board = game.board.lock().unwrap().clone();
+        assert!(take_draw_offer(&board, &game).is_ok());
+        assert_eq!(
+            take_draw_offer(&board, &game).err(),
+            Some(ChessError::NoDrawOffered)
+        );
+    }
+
+    #[test]
+    fn engine_answers_draw_offers() {
+        let game = Game::init();
+        *game.board.lock().unwrap() = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        game.player.lock().unwrap().white = Some(false);
+        assert_eq!(engine_answers_draw(&game), Ok(false));
+        game.player.lock().unwrap().white = Some(true);
+        assert_eq!(engine_answers_draw(&game), Ok(true));
+        assert_eq!(
+            game.board.lock().unwrap().status,
+            GameStatus::Draw(DrawReason::Agreement)
+        );
+    }
+
+    #[test]
+    fn busy_engine_refuses_requests() {
+        let engine = ExternalEngine::default();
+        let process = engine.process.lock().unwrap();
+        assert_eq!(engine.try_process().err(), Some(ChessError::EngineBusy));
+        drop(process);
+        assert!(engine.try_process().unwrap().is_none());
+    }
+
+    #[test]
+    fn parses_uci_moves() {
+        let m = Move::from_uci("e7e8q").unwrap();
+        assert_eq!(m.promotion, Some(FigureType::Queen));
+        assert_eq!(m.to_uci(), "e7e8q");
+        assert_eq!(Move::from_uci("g1f3").unwrap().to_uci(), "g1f3");
+        for invalid in ["", "e2", "e2e9", "e7e8Q", "e7e8x", "e2e4e"] {
+            assert_eq!(Move::from_uci(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn flag_fall_ends_game() {
+        let mut board = Board::init();
+        let mut clock = Some(Clock::new(TimeControl {
+            base_ms: 0,
+            increment_ms: 0,
+            increment_kind: chess_core::clock::IncrementKind::Fischer,
+        }));
+        assert_eq!(ensure_ongoing(&mut board, &mut clock), Ok(()));
+        assert_eq!(board.status, GameStatus::Ongoing);
+
+        run_clock(&board, &mut clock);
+        assert_eq!(
+            ensure_ongoing(&mut board, &mut clock),
+            Err(ChessError::GameOver)
+        );
+        assert_eq!(board.status, GameStatus::Timeout);
+        assert_eq!(clock.unwrap().times(Instant::now()).running, None);
+        assert!(History::new(&board)
+            .to_pgn(&board)
+            .contains("[Result \"0-1\"]"));
+    }
+
+    #[test]
+    fn flag_fall_against_lone_king_draws() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4KR2 w - - 0 1").unwrap();
+        let mut clock = Some(Clock::new(TimeControl {
+            base_ms: 0,
+            increment_ms: 0,
+            increment_kind: chess_core::clock::IncrementKind::Fischer,
+        }));
+        run_clock(&board, &mut clock);
+        check_flag(&mut board, &mut clock);
+        assert_eq!(
+            board.status,
+            GameStatus::Draw(DrawReason::TimeoutVsInsufficientMaterial)
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! All games open in the app, each with its own board, clock and autosave file.
+use crate::error::ChessError;
+use crate::events::Emitter;
+use crate::{lock, Game};
+use chess_core::GameStatus;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+
+pub(crate) type GameId = u32;
+
+/// What the game list shows about a game.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub(crate) struct GameSummary {
+    id: GameId,
+    status: GameStatus,
+    round: i32,
+    /// Whether the game has started and is not decided yet
+    unfinished: bool,
+    /// Whether this is the game the board window shows
+    current: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct Games {
+    registry: Mutex<Registry>,
+    /// Directory every game is saved to after each change, `None` to not save them
+    autosave_dir: Option<PathBuf>,
+    /// App whose windows are told about changes to the games
+    app: Option<AppHandle>,
+}
+
+#[derive(Default)]
+struct Registry {
+    games: BTreeMap<GameId, Arc<Game>>,
+    next_id: GameId,
+    current: Option<GameId>,
+}
+
+impl Games {
+    /// Continues the unfinished games saved in `autosave_dir` and forgets the decided ones, leaving
+    /// files that can't be read alone.
+    pub(crate) fn restore(autosave_dir: Option<PathBuf>, app: Option<AppHandle>) -> Games {
+        let mut registry = Registry::default();
+        let entries = autosave_dir
+            .as_deref()
+            .and_then(|dir| fs::read_dir(dir).ok())
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok);
+        for entry in entries {
+            let path = entry.path();
+            let id = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<GameId>().ok());
+            let id = match id {
+                Some(id) => id,
+                None => continue,
+            };
+            match Game::restore(Some(path.clone())) {
+                Ok(mut game) if game.is_unfinished() => {
+                    game.events = Emitter::new(app.clone(), id);
+                    registry.games.insert(id, Arc::new(game));
+                }
+                Ok(_) => {
+                    if let Err(e) = fs::remove_file(&path) {
+                        eprintln!("could not remove {}: {}", path.display(), e);
+                    }
+                    continue;
+                }
+                Err(message) => eprintln!("could not restore game {}: {}", id, message),
+            }
+            // Files left on disk keep their id, so no new game overwrites them
+            registry.next_id = registry.next_id.max(id + 1);
+        }
+        registry.current = registry.games.keys().next_back().copied();
+        Games {
+            registry: Mutex::new(registry),
+            autosave_dir,
+            app,
+        }
+    }
+
+    /// Starts a new game from the initial position and switches to it.
+    pub(crate) fn create(&self) -> GameId {
+        let mut registry = lock(&self.registry);
+        let id = registry.next_id;
+        registry.next_id += 1;
+        let game = Game {
+            autosave: Mutex::new(self.autosave_file(id)),
+            events: Emitter::new(self.app.clone(), id),
+            ..Game::init()
+        };
+        registry.games.insert(id, Arc::new(game));
+        registry.current = Some(id);
+        id
+    }
+
+    pub(crate) fn get(&self, id: GameId) -> Result<Arc<Game>, ChessError> {
+        lock(&self.registry)
+            .games
+            .get(&id)
+            .cloned()
+            .ok_or(ChessError::UnknownGame { game_id: id })
+    }
+
+    pub(crate) fn list(&self) -> Vec<GameSummary> {
+        let registry = lock(&self.registry);
+        registry
+            .games
+            .iter()
+            .map(|(&id, game)| {
+                let (status, round) = {
+                    let board = lock(&game.board);
+                    (board.status, board.round)
+                };
+                GameSummary {
+                    id,
+                    status,
+                    round,
+                    unfinished: game.is_unfinished(),
+                    current: registry.current == Some(id),
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn current(&self) -> Option<GameId> {
+        lock(&self.registry).current
+    }
+
+    pub(crate) fn switch(&self, id: GameId) -> Result<(), ChessError> {
+        let mut registry = lock(&self.registry);
+        if !registry.games.contains_key(&id) {
+            return Err(ChessError::UnknownGame { game_id: id });
+        }
+        registry.current = Some(id);
+        Ok(())
+    }
+
+    /// Removes a game together with its autosave file.
+    pub(crate) fn close(&self, id: GameId) -> Result<(), ChessError> {
+        let mut registry = lock(&self.registry);
+        let game = registry
+            .games
+            .remove(&id)
+            .ok_or(ChessError::UnknownGame { game_id: id })?;
+        if registry.current == Some(id) {
+            registry.current = None;
+        }
+        // Taking the path first keeps anyone still holding the game from saving it again
+        let autosave = lock(&game.autosave).take();
+        if let Some(path) = autosave.as_deref().filter(|path| path.exists()) {
+            fs::remove_file(path).map_err(|e| ChessError::Storage {
+                message: format!("{}: {}", path.display(), e),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Tells the windows the remaining time of every running clock.
+    pub(crate) fn tick(&self) {
+        let games: Vec<_> = lock(&self.registry).games.values().cloned().collect();
+        for game in games {
+            game.tick();
+        }
+    }
+
+    fn autosave_file(&self, id: GameId) -> Option<PathBuf> {
+        self.autosave_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::square;
+
+    #[test]
+    fn keeps_games_apart() {
+        let games = Games::default();
+        let first = games.create();
+        let second = games.create();
+        assert_ne!(first, second);
+        assert_eq!(games.current(), Some(second));
+
+        let game = games.get(first).unwrap();
+        assert_eq!(
+            crate::play_move(&game, square("e2"), square("e4"), None),
+            Ok(())
+        );
+        assert_eq!(games.get(second).unwrap().board.lock().unwrap().round, 0);
+        assert_eq!(games.switch(first), Ok(()));
+        let list = games.list();
+        assert_eq!(list.len(), 2);
+        assert!(list[0].unfinished && list[0].current);
+        assert!(!list[1].unfinished && !list[1].current);
+
+        assert_eq!(games.close(first), Ok(()));
+        assert_eq!(games.current(), None);
+        assert_eq!(
+            games.get(first).err(),
+            Some(ChessError::UnknownGame { game_id: first })
+        );
+        assert_eq!(games.switch(7), Err(ChessError::UnknownGame { game_id: 7 }));
+    }
+
+    #[test]
+    fn restores_unfinished_games() {
+        let dir = std::env::temp_dir().join(format!("tauri_chess_games_{}", std::process::id()));
+        let games = Games::restore(Some(dir.clone()), None);
+        for _ in 0..3 {
+            games.create();
+        }
+        crate::play_move(&games.get(0).unwrap(), square("d2"), square("d4"), None).unwrap();
+        crate::play_move(&games.get(2).unwrap(), square("e2"), square("e4"), None).unwrap();
+        games.get(2).unwrap().board.lock().unwrap().status =
+            GameStatus::Resignation { white: true };
+        games.get(2).unwrap().changed();
+
+        let restored = Games::restore(Some(dir.clone()), None);
+        let ids: Vec<_> = restored.list().iter().map(|game| game.id).collect();
+        assert_eq!(ids, [0]);
+        assert_eq!(restored.current(), Some(0));
+        assert_eq!(restored.create(), 1);
+        assert!(!dir.join("2.json").exists());
+
+        let game = restored.get(0).unwrap();
+        assert_eq!(restored.close(0), Ok(()));
+        game.changed();
+        assert!(!dir.join("0.json").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_unreadable_saves() {
+        let dir = std::env::temp_dir().join(format!("tauri_chess_broken_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("0.json"), "not a game").unwrap();
+
+        let games = Games::restore(Some(dir.clone()), None);
+        assert!(games.list().is_empty());
+        assert_eq!(
+            fs::read_to_string(dir.join("0.json")).unwrap(),
+            "not a game"
+        );
+        assert_eq!(games.create(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
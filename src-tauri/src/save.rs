@@ -0,0 +1,201 @@
+//! Games saved to disk as versioned JSON. The moves are replayed on loading, so a save file
+//! stays valid when the internals of `Board` change.
+use crate::Player;
+use chess_core::clock::{Clock, ClockTimes, TimeControl};
+use chess_core::history::History;
+use chess_core::{Board, GameStatus, Move};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+/// Version of the save file format, raised on incompatible changes
+const VERSION: u32 = 1;
+/// Directory in the app data directory every open game is saved to after each move, as
+/// `<game id>.json`
+pub(crate) const AUTOSAVE_DIR: &str = "games";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct SavedGame {
+    version: u32,
+    start_fen: String,
+    /// Moves in UCI notation, a pawn waiting for promotion without the new kind
+    moves: Vec<String>,
+    /// Status the moves can't tell, like a resignation
+    status: GameStatus,
+    /// Color of the human player when playing against the engine
+    player_white: Option<bool>,
+    time_control: Option<TimeControl>,
+    /// Remaining time when the game was saved
+    clock: Option<ClockTimes>,
+}
+
+/// Everything restored from a save file.
+pub(crate) struct Restored {
+    pub(crate) board: Board,
+    pub(crate) history: History,
+    pub(crate) player: Player,
+    /// Stopped clocks, to be started once the player is back
+    pub(crate) clock: Option<Clock>,
+}
+
+impl SavedGame {
+    pub(crate) fn new(
+        board: &Board,
+        history: &History,
+        player: &Player,
+        clock: &Option<Clock>,
+    ) -> SavedGame {
+        let moves = history
+            .moves
+            .iter()
+            .map(|played| {
+                Move {
+                    from: played.from,
+                    to: played.to,
+                    promotion: played.promotion.clone(),
+                }
+                .to_uci()
+            })
+            .collect();
+        SavedGame {
+            version: VERSION,
+            start_fen: history.start_fen.clone(),
+            moves,
+            status: board.status,
+            player_white: player.white,
+            time_control: clock.as_ref().map(Clock::time_control),
+            clock: clock.as_ref().map(|clock| clock.times(Instant::now())),
+        }
+    }
+
+    pub(crate) fn from_json(json: &str) -> Result<SavedGame, String> {
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+        let versioned: Versioned = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if versioned.version != VERSION {
+            return Err(format!("unsupported version {}", versioned.version));
+        }
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("saved games are always serializable")
+    }
+
+    pub(crate) fn read(path: &Path) -> Result<SavedGame, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        SavedGame::from_json(&json)
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        fs::write(path, self.to_json()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Replays the moves from the starting position.
+    pub(crate) fn restore(&self) -> Result<Restored, String> {
+        let mut board = Board::from_fen(&self.start_fen)?;
+        let mut history = History::new(&board);
+        for uci in &self.moves {
+            let m = Move::from_uci(uci).ok_or_else(|| format!("invalid move {}", uci))?;
+            let legal = board.pending_promotion.is_none()
+                && !board.status.is_decided()
+                && board.legal_moves().iter().any(|legal| {
+                    legal.from == m.from
+                        && legal.to == m.to
+                        && (m.promotion.is_none() || legal.promotion == m.promotion)
+                });
+            if !legal {
+                return Err(format!("illegal move {}", uci));
+            }
+            history.play(&mut board, m.from, m.to, m.promotion);
+        }
+        if self.status.is_decided() && !board.status.is_decided() {
+            board.status = self.status;
+        }
+        let clock = match (self.time_control, self.clock) {
+            (Some(time_control), Some(times)) => Some(Clock::resume(time_control, times)),
+            (Some(time_control), None) => Some(Clock::new(time_control)),
+            (None, _) => None,
+        };
+        Ok(Restored {
+            board,
+            history,
+            player: Player {
+                white: self.player_white,
+            },
+            clock,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_core::clock::IncrementKind;
+    use chess_core::draw::DrawReason;
+    use chess_core::pgn::from_pgn;
+
+    #[test]
+    fn restores_saved_game() {
+        let (mut board, history) = from_pgn("1. e4 d5 2. exd5 Qxd5 3. Nc3").unwrap();
+        board.status = GameStatus::Draw(DrawReason::Agreement);
+        let clock = Some(Clock::new(TimeControl {
+            base_ms: 60_000,
+            increment_ms: 0,
+            increment_kind: IncrementKind::Fischer,
+        }));
+        let player = Player { white: Some(false) };
+        let saved = SavedGame::new(&board, &history, &player, &clock);
+        assert_eq!(saved.moves, ["e2e4", "d7d5", "e4d5", "d8d5", "b1c3"]);
+
+        let json = saved.to_json();
+        assert!(json.contains("\"version\": 1"));
+        let loaded = SavedGame::from_json(&json).unwrap();
+        assert_eq!(loaded, saved);
+        let restored = loaded.restore().unwrap();
+        assert_eq!(restored.board.to_fen(), board.to_fen());
+        assert_eq!(restored.board.status, board.status);
+        assert_eq!(restored.board.repetitions(), 1);
+        assert_eq!(
+            restored.history.to_pgn(&restored.board),
+            history.to_pgn(&board)
+        );
+        assert_eq!(restored.player.white, Some(false));
+        let times = restored.clock.unwrap().times(Instant::now());
+        assert_eq!((times.white_ms, times.black_ms), (60_000, 60_000));
+    }
+
+    #[test]
+    fn restores_pending_promotion() {
+        let mut board = Board::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut history = History::new(&board);
+        let m = Move::from_uci("b7b8").unwrap();
+        history.play(&mut board, m.from, m.to, None);
+        let saved = SavedGame::new(&board, &history, &Player::default(), &None);
+        let restored = saved.restore().unwrap();
+        assert_eq!(restored.board.pending_promotion, board.pending_promotion);
+        assert!(restored.clock.is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        assert!(SavedGame::from_json("{}").is_err());
+        assert!(SavedGame::from_json("{\"version\": 2}")
+            .unwrap_err()
+            .contains("version 2"));
+        let mut saved = SavedGame::new(
+            &Board::init(),
+            &History::new(&Board::init()),
+            &Player::default(),
+            &None,
+        );
+        saved.moves = vec!["e2e5".to_string()];
+        assert_eq!(saved.restore().err(), Some("illegal move e2e5".to_string()));
+    }
+}
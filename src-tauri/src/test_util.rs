@@ -0,0 +1,7 @@
+//! Shorthands shared by the tests.
+use chess_core::Position;
+
+/// Position of a square in algebraic notation like `"e4"`.
+pub(crate) fn square(square: &str) -> Position {
+    Position::from_square(square).unwrap()
+}
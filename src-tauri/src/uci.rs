@@ -0,0 +1,308 @@
+//! Client side of the Universal Chess Interface, to analyze positions with an external engine
+//! like Stockfish running as a child process.
+use chess_core::history::History;
+use chess_core::Move;
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long the engine may take to answer `uci` and `isready`.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Extra time on top of the requested search time before the engine is considered hung.
+const BESTMOVE_GRACE: Duration = Duration::from_secs(5);
+/// How long the engine may take to exit after `quit` before it is killed.
+const QUIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Score {
+    /// Centipawns from the point of view of the side to move
+    Centipawns(i32),
+    /// Moves until mate, negative if the side to move gets mated
+    Mate(i32),
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub(crate) struct Analysis {
+    /// Best move in long algebraic notation, like `e2e4` or `e7e8q`
+    pub(crate) best_move: String,
+    pub(crate) ponder: Option<String>,
+    pub(crate) score: Option<Score>,
+    pub(crate) depth: Option<u32>,
+    pub(crate) pv: Vec<String>,
+}
+
+/// A running UCI engine process. The engine is told to quit when this is dropped.
+pub(crate) struct UciEngine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    pub(crate) name: Option<String>,
+}
+
+impl UciEngine {
+    /// Starts the engine and waits until it is ready for a search.
+    pub(crate) fn spawn(program: &str, args: &[&str]) -> Result<UciEngine, String> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("could not start engine '{}': {}", program, e))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        // Reading happens on its own thread so a silent engine can't block forever
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = UciEngine {
+            child,
+            stdin,
+            lines,
+            name: None,
+        };
+        engine.send("uci")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            let line = engine.read_line(deadline)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = Some(name.trim().to_string());
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        engine.wait_ready()?;
+        Ok(engine)
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| format!("could not write to engine: {}", e))
+    }
+
+    fn read_line(&self, deadline: Instant) -> Result<String, String> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err("engine did not answer in time".to_string()),
+            Err(RecvTimeoutError::Disconnected) => Err("engine exited".to_string()),
+        }
+    }
+
+    fn wait_ready(&mut self) -> Result<(), String> {
+        self.send("isready")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        while self.read_line(deadline)?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    /// Searches the current position of the game for the given time.
+    pub(crate) fn analyze(
+        &mut self,
+        history: &History,
+        time: Duration,
+    ) -> Result<Analysis, String> {
+        self.send(&position_command(history))?;
+        self.wait_ready()?;
+        self.send(&format!("go movetime {}", time.as_millis()))?;
+
+        let deadline = Instant::now() + time + BESTMOVE_GRACE;
+        let mut score = None;
+        let mut depth = None;
+        let mut pv = vec![];
+        loop {
+            let line = self.read_line(deadline)?;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("info") => {
+                    let info = parse_info(tokens);
+                    // Lines with only `currmove` or `nodes` don't replace the last evaluation
+                    if info.score.is_some() {
+                        score = info.score;
+                        depth = info.depth.or(depth);
+                        if !info.pv.is_empty() {
+                            pv = info.pv;
+                        }
+                    }
+                }
+                Some("bestmove") => {
+                    let best_move = tokens
+                        .next()
+                        .ok_or_else(|| "engine sent bestmove without a move".to_string())?;
+                    if best_move == "(none)" || best_move == "0000" {
+                        return Err("engine found no move".to_string());
+                    }
+                    let ponder = match (tokens.next(), tokens.next()) {
+                        (Some("ponder"), Some(m)) => Some(m.to_string()),
+                        _ => None,
+                    };
+                    return Ok(Analysis {
+                        best_move: best_move.to_string(),
+                        ponder,
+                        score,
+                        depth,
+                        pv,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The `position` command for the game: its start position and the moves played since.
+pub(crate) fn position_command(history: &History) -> String {
+    let mut command = format!("position fen {}", history.start_fen);
+    if !history.moves.is_empty() {
+        command.push_str(" moves");
+        for played in &history.moves {
+            let m = Move {
+                from: played.from,
+                to: played.to,
+                promotion: played.promotion.clone(),
+            };
+            command.push(' ');
+            command.push_str(&m.to_uci());
+        }
+    }
+    command
+}
+
+#[derive(Default)]
+struct Info {
+    score: Option<Score>,
+    depth: Option<u32>,
+    pv: Vec<String>,
+}
+
+fn parse_info<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Info {
+    let mut info = Info::default();
+    while let Some(token) = tokens.next() {
+        match token {
+            "depth" => info.depth = tokens.next().and_then(|d| d.parse().ok()),
+            "score" => {
+                info.score = match (tokens.next(), tokens.next().and_then(|s| s.parse().ok())) {
+                    (Some("cp"), Some(cp)) => Some(Score::Centipawns(cp)),
+                    (Some("mate"), Some(moves)) => Some(Score::Mate(moves)),
+                    _ => None,
+                }
+            }
+            // The principal variation is always the last field
+            "pv" => info.pv = tokens.by_ref().map(str::to_string).collect(),
+            // Free text that could contain any of the keywords above
+            "string" => break,
+            _ => {}
+        }
+    }
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_core::pgn::from_pgn;
+    use chess_core::Board;
+
+    /// A stand-in engine that always proposes e2e4 and echoes the position it got.
+    const SCRIPTED_ENGINE: &str = r#"
+        while read -r line; do
+            case "$line" in
+                uci) echo "id name Scripted Engine"; echo "uciok" ;;
+                isready) echo "readyok" ;;
+                position*) echo "info string $line" ;;
+                go*)
+                    echo "info depth 1 score cp 12 nodes 20 pv d2d4"
+                    echo "info depth 2 currmove e2e4 currmovenumber 1"
+                    echo "info depth 2 seldepth 3 score cp 31 lowerbound pv e2e4 e7e5"
+                    echo "bestmove e2e4 ponder e7e5" ;;
+                quit) exit 0 ;;
+            esac
+        done
+    "#;
+
+    fn scripted_engine() -> UciEngine {
+        UciEngine::spawn("sh", &["-c", SCRIPTED_ENGINE]).unwrap()
+    }
+
+    #[test]
+    fn position_command_from_history() {
+        let history = History::new(&Board::init());
+        assert_eq!(
+            position_command(&history),
+            "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+
+        let (_, history) = from_pgn(
+            "[SetUp \"1\"]\n[FEN \"4k3/1P6/8/8/8/8/8/4K2R w K - 0 1\"]\n\n1. O-O Kd7 2. b8=N+",
+        )
+        .unwrap();
+        assert_eq!(
+            position_command(&history),
+            "position fen 4k3/1P6/8/8/8/8/8/4K2R w K - 0 1 moves e1g1 e8d7 b7b8n"
+        );
+    }
+
+    #[test]
+    fn parses_info_lines() {
+        let info =
+            parse_info("depth 12 seldepth 18 score mate -3 nodes 1000 pv e7e5 g1f3".split(' '));
+        assert_eq!(info.depth, Some(12));
+        assert_eq!(info.score, Some(Score::Mate(-3)));
+        assert_eq!(info.pv, vec!["e7e5", "g1f3"]);
+
+        let info = parse_info("string depth 3 score cp 20".split(' '));
+        assert_eq!(info.score, None);
+    }
+
+    #[test]
+    fn analyze_with_scripted_engine() {
+        let mut engine = scripted_engine();
+        assert_eq!(engine.name.as_deref(), Some("Scripted Engine"));
+        let history = History::new(&Board::init());
+        let analysis = engine.analyze(&history, Duration::from_millis(10)).unwrap();
+        assert_eq!(
+            analysis,
+            Analysis {
+                best_move: "e2e4".to_string(),
+                ponder: Some("e7e5".to_string()),
+                score: Some(Score::Centipawns(31)),
+                depth: Some(2),
+                pv: vec!["e2e4".to_string(), "e7e5".to_string()],
+            }
+        );
+        // The engine stays usable for the next position
+        assert!(engine.analyze(&history, Duration::from_millis(10)).is_ok());
+    }
+
+    #[test]
+    fn engine_errors() {
+        assert!(UciEngine::spawn("/nonexistent/engine", &[]).is_err());
+        // Exits before the handshake is done
+        assert!(UciEngine::spawn("sh", &["-c", "echo 'id name Broken'"]).is_err());
+    }
+}
@@ -1,27 +1,67 @@
 const { invoke } = window.__TAURI__.tauri;
+const { listen } = window.__TAURI__.event;
 var canva = document.getElementById("board");
 var canva2 = document.getElementById("board2");
 
 var canvas_length = window.screen.height / 2;
 var rect_length = canvas_length / 8;
 var first_canva = true;
+// Game of the registry this window shows, see current_game
+var gameId = null;
+// Position last drawn, from get_board or the last position-changed event
+var lastBoard = null;
+// Draw offered by one of two human players, from the last draw-offered event
+var drawOffer = null;
 
 window.addEventListener("load", () => {
-  redrawBoard()
+  invoke("current_game").then(id => {
+    if (id === null) {
+      window.location.replace("index.html");
+      return;
+    }
+    gameId = id;
+    redrawBoard()
+    invoke("get_remaining_time", { gameId: gameId }).then(drawClock)
+  })
 });
 
+// The Rust side tells every window about changes to the games, see events.rs
+listen("position-changed", event => {
+  if (event.payload.game_id === gameId) {
+    drawBoard(event.payload);
+  }
+})
+
+listen("move-made", event => {
+  if (event.payload.game_id === gameId) {
+    document.getElementById("lastMove").textContent = "Last move: " + event.payload.san;
+  }
+})
+
+listen("draw-offered", event => {
+  if (event.payload.game_id === gameId) {
+    drawOffer = event.payload;
+    drawDrawOffer(lastBoard);
+  }
+})
+
+listen("clock-tick", event => {
+  if (event.payload.game_id === gameId) {
+    drawClock(event.payload.times);
+  }
+})
+
 canva.addEventListener('click', e => {
   let position = { 'x': Math.floor(e.offsetX / rect_length), 'y': Math.floor(e.offsetY / rect_length) }
 
   positionInteraction(position).then(
-    _ => {})
-  redrawBoard();
+    _ => showError(null)
+  ).catch(showError)
+  drawBoard(lastBoard);
       getOptions(position).then(
         options => {
-          if (options !== null) {
-            drawOptions(options.movable, "orange")
-            drawOptions(options.killable, "red")
-          }
+          drawOptions(options.movable, "orange")
+          drawOptions(options.killable, "red")
         }
       ).catch(error =>
         console.log(error, "could not fetch options or not correct turn")
@@ -32,37 +72,126 @@ canva2.addEventListener('click', e => {
   let position = { 'x': Math.floor(e.offsetX / rect_length), 'y': Math.floor(e.offsetY / rect_length) }
 
   positionInteraction(position).then(
-    _ => {})
-  redrawBoard();
+    _ => showError(null)
+  ).catch(showError)
+  drawBoard(lastBoard);
       getOptions(position).then(
         options => {
-          if (options !== null) {
-            drawOptions(options.movable, "orange")
-            drawOptions(options.killable, "red")
-          }
+          drawOptions(options.movable, "orange")
+          drawOptions(options.killable, "red")
         }
       ).catch(error =>
         console.log(error, "could not fetch options or not correct turn")
       );
 })
 
+document.querySelectorAll("#promotion button").forEach((button) => {
+  button.addEventListener('click', _ => {
+    promote(button.value).catch(showError)
+  })
+})
+
+document.getElementById("undo").addEventListener('click', _ => {
+  invoke("undo_move", { gameId: gameId })
+})
+
+document.getElementById("redo").addEventListener('click', _ => {
+  invoke("redo_move", { gameId: gameId })
+})
+
+document.getElementById("claimDraw").addEventListener('click', _ => {
+  invoke("claim_draw", { gameId: gameId }).catch(showError)
+})
+
+document.getElementById("offerDraw").addEventListener('click', _ => {
+  invoke("offer_draw", { gameId: gameId }).then(accepted => {
+    let text = document.getElementById("turn");
+    if (drawOffer !== null && drawOffer.round === lastBoard.round) {
+      text.textContent = "Draw offered, your opponent answers after your move";
+    } else if (!accepted) {
+      text.textContent = "The draw offer was declined";
+    }
+  }).catch(showError)
+})
+
+document.getElementById("acceptDraw").addEventListener('click', _ => {
+  invoke("accept_draw", { gameId: gameId }).catch(showError)
+  drawOffer = null;
+  drawDrawOffer(lastBoard);
+})
+
+document.getElementById("declineDraw").addEventListener('click', _ => {
+  invoke("decline_draw", { gameId: gameId }).catch(showError)
+  drawOffer = null;
+  drawDrawOffer(lastBoard);
+})
+
+document.getElementById("closeGame").addEventListener('click', _ => {
+  invoke("close_game", { gameId: gameId }).then(
+    _ => window.location.replace("index.html")
+  ).catch(showError)
+})
+
+document.getElementById("resign").addEventListener('click', _ => {
+  invoke("resign", { gameId: gameId }).catch(showError)
+})
+
 // Rust invokes
 async function positionInteraction(position){
-  let c = await invoke("position_interaction", position);
+  let c = await invoke("position_interaction", { gameId: gameId, ...position });
   return c;
 }
 
+async function promote(kind) {
+  await invoke("promote", { gameId: gameId, kind: kind });
+}
+
 async function getBoard() {
-  let figures = await invoke("get_board");
+  let figures = await invoke("get_board", { gameId: gameId });
   return figures;
 }
 
 async function getOptions(position) {
-  let options = await invoke("get_options", position);
+  let options = await invoke("get_options", { gameId: gameId, ...position });
   return options;
 }
 
 
+// Explains why a command was refused, see ChessError
+function showError(error) {
+  let text = document.getElementById("error");
+  if (error === null) {
+    text.textContent = "";
+    return;
+  }
+  switch (error.kind) {
+    case "NotYourTurn":
+      text.textContent = "It is not your turn";
+      break;
+    case "NoPieceAtSquare":
+      text.textContent = "There is no piece on that square";
+      break;
+    case "IllegalMove":
+      text.textContent = "That move is illegal";
+      break;
+    case "GameOver":
+      text.textContent = "The game is over";
+      break;
+    case "PromotionPending":
+      text.textContent = "Choose a piece for the pawn first";
+      break;
+    case "NoDrawToClaim":
+      text.textContent = "There is no draw to claim";
+      break;
+    case "NoDrawOffered":
+      text.textContent = "The draw offer is no longer open";
+      break;
+    default:
+      text.textContent = "Something went wrong";
+      console.log(error);
+  }
+}
+
 // Drawing to canvas
 function drawFigures(board) {
   board.figures.forEach((figure) => {
@@ -72,6 +201,81 @@ function drawFigures(board) {
   });
 }
 
+function drawStatus(board) {
+  let color = board.round % 2 == 0 ? "White" : "Black";
+  let text = document.getElementById("turn");
+  switch (board.status) {
+    case "Checkmate":
+      text.textContent = "Checkmate, " + color + " lost";
+      break;
+    case "Stalemate":
+      text.textContent = "Stalemate";
+      break;
+    case "Timeout":
+      text.textContent = color + " lost on time";
+      break;
+    case "Check":
+      text.textContent = "It is " + color + "s turn, check!";
+      break;
+    default:
+      text.textContent = "It is " + color + "s turn";
+  }
+  if (board.status.Resignation !== undefined) {
+    text.textContent = (board.status.Resignation.white ? "White" : "Black") + " resigned";
+  }
+  if (board.status.Draw !== undefined) {
+    text.textContent = "Draw by " + drawReasons[board.status.Draw];
+  }
+}
+
+const drawReasons = {
+  ThreefoldRepetition: "threefold repetition",
+  FivefoldRepetition: "fivefold repetition",
+  FiftyMoveRule: "the fifty-move rule",
+  SeventyFiveMoveRule: "the seventy-five-move rule",
+  InsufficientMaterial: "insufficient material",
+  Agreement: "agreement",
+  TimeoutVsInsufficientMaterial: "timeout without mating material",
+};
+
+function formatTime(ms) {
+  let seconds = Math.ceil(ms / 1000);
+  return Math.floor(seconds / 60) + ":" + String(seconds % 60).padStart(2, "0");
+}
+
+function drawClock(times) {
+  let clock = document.getElementById("clock");
+  if (times === null) {
+    clock.classList.add("hidden");
+    return;
+  }
+  clock.classList.remove("hidden");
+  let white = (times.running === true ? "> " : "") + "White " + formatTime(times.white_ms);
+  let black = (times.running === false ? "> " : "") + "Black " + formatTime(times.black_ms);
+  clock.textContent = white + " | " + black;
+}
+
+function drawPromotion(board) {
+  let promotion = document.getElementById("promotion");
+  if (board.pending_promotion !== null) {
+    promotion.classList.remove("hidden");
+  } else {
+    promotion.classList.add("hidden");
+  }
+}
+
+// The opponent answers a draw offer right after the offering player moved
+function drawDrawOffer(board) {
+  let offer = document.getElementById("drawOffer");
+  if (board !== null && drawOffer !== null && board.round === drawOffer.round + 1) {
+    let color = drawOffer.white ? "White" : "Black";
+    document.getElementById("drawOfferText").textContent = color + " offers a draw";
+    offer.classList.remove("hidden");
+  } else {
+    offer.classList.add("hidden");
+  }
+}
+
 function drawFigure(figure) {
   let color = figure.white ? "#FFDAB9" : "#8B5742";
   let circle = drawCircle(color, figure.position.x, figure.position.y, rect_length / 2.5);
@@ -95,16 +299,24 @@ function clearBoard() {
 }
 
 function redrawBoard() {
+  getBoard().then(drawBoard).catch(error =>
+    console.log(error, "could not fetch board!!! ")
+  )
+}
+
+function drawBoard(board) {
+  if (board === null) {
+    return;
+  }
+  lastBoard = board;
   var c = canva;
   canva = canva2;
   canva2 = c;
   fillBoard();
-  getBoard().then(
-    board => {
-      drawFigures(board)
-    }).catch(error =>
-      console.log(error, "could not fetch board!!! ")
-    )
+  drawFigures(board)
+  drawStatus(board)
+  drawPromotion(board)
+  drawDrawOffer(board)
   canva.classList.remove("hidden");
   canva2.classList.add("hidden");
   clearBoard();
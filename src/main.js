@@ -4,15 +4,44 @@ async function greet() {
   await invoke("greet", { name: greetInputEl.value });
 }
 
-async function setPlayerColor() {
+async function setTimeControl(gameId) {
+  let value = document.forms["playerColor"]["timeControl"].value;
+  let timeControl = null;
+  if (value !== "") {
+    let [base, increment, kind] = value.split(",");
+    timeControl = { base_ms: Number(base), increment_ms: Number(increment), increment_kind: kind };
+  }
+  await invoke("set_time_control", {gameId: gameId, timeControl: timeControl});
+}
+
+async function setPlayerColor(gameId) {
   let color = document.forms["playerColor"]["color"].value === "true";
-  invoke("set_player_color", {white: color});
+  await invoke("set_player_color", {gameId: gameId, white: color});
+}
+
+// Offers to continue every unfinished game
+async function listGames() {
+  let games = await invoke("list_games");
+  let list = document.querySelector("#games");
+  games.filter(game => game.unfinished).forEach(game => {
+    let button = document.createElement("button");
+    button.textContent = "Continue game " + game.id + " at move " + (Math.floor(game.round / 2) + 1);
+    button.addEventListener("click", () => {
+      invoke("switch_game", {gameId: game.id})
+        .then(_ => invoke("resume_game", {gameId: game.id}))
+        .then(_ => window.location.replace("chess_board.html"));
+    });
+    list.appendChild(button);
+  });
 }
 
 window.addEventListener("DOMContentLoaded", () => {
+  listGames();
   document.querySelector("#playerColor").addEventListener("submit", (e) => {
     e.preventDefault();
-    setPlayerColor();
-    window.location.replace("chess_board.html");
+    // The clock has to run before the engine may make the first move
+    invoke("create_game")
+      .then(gameId => setTimeControl(gameId).then(_ => setPlayerColor(gameId)))
+      .then(_ => window.location.replace("chess_board.html"));
   });
 });